@@ -0,0 +1,317 @@
+use crate::{symmetry::SymmetryMap, CellProbability};
+
+/// Offsets of the neighbours of a cell, the first four are the direct neighbours.
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+/// Which cells touching each other belong to the same part of the shape.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Connectivity {
+    /// Only cells sharing a side are connected.
+    Four,
+    /// Cells sharing a side or a corner are connected.
+    #[default]
+    Eight,
+}
+
+impl Connectivity {
+    /// Offsets of the cells that are connected to a cell.
+    pub(crate) fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &NEIGHBOURS[..4],
+            Connectivity::Eight => &NEIGHBOURS,
+        }
+    }
+}
+
+/// Cleanup of the randomly sampled shape before the edges are generated.
+///
+/// Only cells that could go either way are changed, so [`crate::MaskValue::Solid`] and
+/// [`crate::MaskValue::Empty`] cells stay the same.
+/// Cells are counted in the mask, so with mirroring a cell covers multiple pixels.
+///
+/// ```
+/// use sprite_gen::{generate, Cleanup, MaskValue, Options, PixelFormat};
+///
+/// let mask = vec![MaskValue::Body1; 16 * 16];
+/// let options = Options {
+///     cleanup: Cleanup {
+///         iterations: 2,
+///         single_component: true,
+///         fill_holes: true,
+///         ..Default::default()
+///     },
+///     pixel_format: PixelFormat::Rgba,
+///     ..Default::default()
+/// };
+/// assert_eq!(generate(&mask, 16, options.clone()).size(), (16, 16));
+///
+/// // Every part is smaller than the whole mask, so nothing is left
+/// let sprite = generate(
+///     &mask,
+///     16,
+///     Options {
+///         cleanup: Cleanup {
+///             min_island_size: 16 * 16 + 1,
+///             ..Default::default()
+///         },
+///         ..options
+///     },
+/// );
+/// assert!(sprite.pixels().iter().all(|pixel| pixel & 0xFF == 0));
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Cleanup {
+    /// Amount of times the cellular automaton smoothing is applied.
+    pub iterations: usize,
+    /// Amount of filled neighbours from `0` - `8` an empty cell needs to become filled.
+    pub birth: u8,
+    /// Amount of filled neighbours from `0` - `8` a filled cell needs to stay filled.
+    pub survival: u8,
+    /// Parts of the shape with fewer cells than this are removed.
+    pub min_island_size: usize,
+    /// Only keep the largest part of the shape.
+    pub single_component: bool,
+    /// How cells are connected when finding parts of the shape.
+    pub connectivity: Connectivity,
+    /// Fill empty areas that are completely enclosed by the shape.
+    pub fill_holes: bool,
+}
+
+impl Cleanup {
+    /// Whether any of the steps changes the shape.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.iterations > 0 || self.min_island_size > 1 || self.single_component || self.fill_holes
+    }
+
+    /// Clean the sampled mask, where `-1` is a border, `0` empty and `1` a body.
+    pub(crate) fn apply(&self, mask: &mut [i8], cells: &[CellProbability], symmetry: &SymmetryMap) {
+        let neighbours = neighbours(mask.len(), symmetry);
+
+        // Cells that are always the same or that are never visible can't change
+        let fixed = cells
+            .iter()
+            .zip(&neighbours)
+            .map(|(cell, neighbours)| {
+                cell.fill <= 0.0 || cell.fill >= 1.0 || neighbours.iter().all(Option::is_none)
+            })
+            .collect::<Vec<_>>();
+        let set = |mask: &mut [i8], index: usize, body: bool| {
+            if fixed[index] {
+                return;
+            }
+
+            mask[index] = match body {
+                true => 1,
+                // Cells that would have been a border otherwise stay a border
+                false if cells[index].border >= 1.0 => -1,
+                false => 0,
+            };
+        };
+
+        for _ in 0..self.iterations {
+            let previous = mask.to_vec();
+            for (index, neighbours) in neighbours.iter().enumerate() {
+                let filled = neighbours
+                    .iter()
+                    .filter(|neighbour| neighbour.is_some_and(|neighbour| previous[neighbour] > 0))
+                    .count() as u8;
+
+                if previous[index] > 0 {
+                    set(mask, index, filled >= self.survival);
+                } else {
+                    set(mask, index, filled >= self.birth);
+                }
+            }
+        }
+
+        if self.fill_holes {
+            // Everything that can't be reached from outside of the sprite is a hole
+            let mut outside = vec![false; mask.len()];
+            let mut stack = (0..mask.len())
+                .filter(|&index| mask[index] <= 0 && neighbours[index][..4].contains(&None))
+                .collect::<Vec<_>>();
+            while let Some(index) = stack.pop() {
+                if outside[index] {
+                    continue;
+                }
+                outside[index] = true;
+
+                stack.extend(
+                    neighbours[index][..4]
+                        .iter()
+                        .flatten()
+                        .filter(|&&neighbour| mask[neighbour] <= 0 && !outside[neighbour]),
+                );
+            }
+
+            for index in 0..mask.len() {
+                if mask[index] <= 0 && !outside[index] {
+                    set(mask, index, true);
+                }
+            }
+        }
+
+        if self.min_island_size > 1 || self.single_component {
+            let connected = self.connectivity.offsets().len();
+            let components = Components::new(mask, &neighbours, connected);
+            let largest = components.largest();
+
+            for index in 0..mask.len() {
+                let Some(component) = components.of(index) else {
+                    continue;
+                };
+
+                let too_small = components.size(component) < self.min_island_size;
+                let not_largest = self.single_component && Some(component) != largest;
+                if too_small || not_largest {
+                    set(mask, index, false);
+                }
+            }
+        }
+    }
+}
+
+impl Default for Cleanup {
+    /// - `iterations`: `0`
+    /// - `birth`: `5`
+    /// - `survival`: `4`
+    /// - `min_island_size`: `0`
+    /// - `single_component`: `false`
+    /// - `connectivity`: `Connectivity::Eight`
+    /// - `fill_holes`: `false`
+    fn default() -> Self {
+        Self {
+            iterations: 0,
+            birth: 5,
+            survival: 4,
+            min_island_size: 0,
+            single_component: false,
+            connectivity: Connectivity::Eight,
+            fill_holes: false,
+        }
+    }
+}
+
+/// Connected parts of the filled cells.
+struct Components {
+    /// Root of the union-find tree of every cell.
+    parents: Vec<usize>,
+    /// Amount of cells of every tree, only valid for the roots.
+    sizes: Vec<usize>,
+    /// Which cells are filled.
+    filled: Vec<bool>,
+}
+
+impl Components {
+    /// Find the parts, only the first `connected` neighbours are used.
+    fn new(mask: &[i8], neighbours: &[[Option<usize>; 8]], connected: usize) -> Self {
+        let mut components = Self {
+            parents: (0..mask.len()).collect(),
+            sizes: vec![1; mask.len()],
+            filled: mask.iter().map(|&cell| cell > 0).collect(),
+        };
+
+        for (index, neighbours) in neighbours.iter().enumerate() {
+            if !components.filled[index] {
+                continue;
+            }
+
+            for &neighbour in neighbours[..connected].iter().flatten() {
+                if components.filled[neighbour] {
+                    components.union(index, neighbour);
+                }
+            }
+        }
+
+        components
+    }
+
+    /// The part a cell belongs to, `None` when it's not filled.
+    fn of(&self, index: usize) -> Option<usize> {
+        self.filled[index].then(|| self.root(index))
+    }
+
+    /// Amount of cells of a part.
+    fn size(&self, component: usize) -> usize {
+        self.sizes[component]
+    }
+
+    /// The part with the most cells.
+    fn largest(&self) -> Option<usize> {
+        (0..self.parents.len())
+            .filter(|&index| self.filled[index] && self.parents[index] == index)
+            .max_by_key(|&index| (self.sizes[index], std::cmp::Reverse(index)))
+    }
+
+    /// Find the root of the tree the cell is part of.
+    fn root(&self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            index = self.parents[index];
+        }
+
+        index
+    }
+
+    /// Merge the trees of both cells.
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.root(a), self.root(b));
+        if a == b {
+            return;
+        }
+
+        // Attach the smaller tree so they stay shallow
+        let (root, child) = if self.sizes[a] >= self.sizes[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[child] = root;
+        self.sizes[root] += self.sizes[child];
+    }
+}
+
+/// The neighbours of every cell of the mask as they appear in the full sprite.
+///
+/// This way the cells at the mirror axes are connected to their mirrored neighbours, `None` is
+/// outside of the sprite.
+fn neighbours(mask_len: usize, symmetry: &SymmetryMap) -> Vec<[Option<usize>; 8]> {
+    // Use the first pixel a cell is copied to
+    let mut pixels = vec![None; mask_len];
+    for (pixel, source) in symmetry.sources.iter().enumerate() {
+        if let Some(source) = *source {
+            pixels[source].get_or_insert(pixel);
+        }
+    }
+
+    pixels
+        .into_iter()
+        .map(|pixel| {
+            let mut neighbours = [None; 8];
+            let Some(pixel) = pixel else {
+                return neighbours;
+            };
+
+            let (x, y) = (pixel % symmetry.width, pixel / symmetry.width);
+            for (neighbour, (dx, dy)) in neighbours.iter_mut().zip(NEIGHBOURS) {
+                let (Some(x), Some(y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy))
+                else {
+                    continue;
+                };
+                if x < symmetry.width && y < symmetry.height {
+                    *neighbour = symmetry.sources[x + y * symmetry.width];
+                }
+            }
+
+            neighbours
+        })
+        .collect()
+}
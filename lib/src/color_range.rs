@@ -0,0 +1,126 @@
+use crate::Error;
+
+/// Limits for the colors of the body.
+///
+/// All values are from `0.0` - `1.0`, the hue wraps around so `0.0` and `1.0` are both red.
+/// The saturation picked with [`crate::Options::saturation`] and the brightness of the gradient
+/// are scaled to fit between the minimum and the maximum.
+///
+/// ```
+/// use sprite_gen::{generate, ColorRange, MaskValue, Options, PixelFormat};
+///
+/// let mask = vec![MaskValue::Body1; 8 * 8];
+/// let options = Options {
+///     // Reds and oranges only
+///     color_range: ColorRange {
+///         hue_start: 0.95,
+///         hue_end: 0.1,
+///         min_lightness: 0.2,
+///         max_lightness: 0.8,
+///         ..Default::default()
+///     },
+///     color_variations: 1.0,
+///     pixel_format: PixelFormat::Rgba,
+///     ..Default::default()
+/// };
+///
+/// for seed in 0..10 {
+///     let sprite = generate(&mask, 8, Options { seed, ..options.clone() });
+///     for pixel in sprite.pixels().iter().filter(|pixel| *pixel & 0xFF > 0) {
+///         let [r, g, b, _] = pixel.to_be_bytes();
+///         assert!(r >= g && r >= b);
+///     }
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ColorRange {
+    /// First hue the body can get.
+    pub hue_start: f32,
+    /// Last hue the body can get, when it's lower than the start the range wraps around `1.0`.
+    pub hue_end: f32,
+    /// Lowest saturation of the body.
+    pub min_saturation: f32,
+    /// Highest saturation of the body.
+    pub max_saturation: f32,
+    /// Lowest lightness of the body.
+    pub min_lightness: f32,
+    /// Highest lightness of the body.
+    pub max_lightness: f32,
+}
+
+impl ColorRange {
+    /// Check whether all values are within `0.0` - `1.0` and the minimums are not above the
+    /// maximums.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        for (name, value) in [
+            ("color_range.hue_start", self.hue_start),
+            ("color_range.hue_end", self.hue_end),
+            ("color_range.min_saturation", self.min_saturation),
+            ("color_range.max_saturation", self.max_saturation),
+            ("color_range.min_lightness", self.min_lightness),
+            ("color_range.max_lightness", self.max_lightness),
+        ] {
+            // Also catches NaN
+            if !(0.0..=1.0).contains(&value) {
+                return Err(Error::OptionOutOfRange { name, value });
+            }
+        }
+
+        if self.min_saturation > self.max_saturation {
+            return Err(Error::InvalidColorRange {
+                reason: "the minimum saturation is higher than the maximum",
+            });
+        }
+        if self.min_lightness > self.max_lightness {
+            return Err(Error::InvalidColorRange {
+                reason: "the minimum lightness is higher than the maximum",
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Map a random value from `0.0` - `1.0` to a hue in the range.
+    pub(crate) fn hue(&self, value: f32) -> f32 {
+        if self.hue_end >= self.hue_start {
+            self.hue_start + value * (self.hue_end - self.hue_start)
+        } else {
+            // Wrap around the red at `1.0`
+            let hue = self.hue_start + value * (self.hue_end + 1.0 - self.hue_start);
+            if hue > 1.0 {
+                hue - 1.0
+            } else {
+                hue
+            }
+        }
+    }
+
+    /// Scale a saturation from `0.0` - `1.0` to fit in the range.
+    pub(crate) fn saturation(&self, saturation: f32) -> f32 {
+        self.min_saturation + saturation * (self.max_saturation - self.min_saturation)
+    }
+
+    /// Scale a lightness from `0.0` - `1.0` to fit in the range.
+    pub(crate) fn lightness(&self, lightness: f32) -> f32 {
+        self.min_lightness + lightness * (self.max_lightness - self.min_lightness)
+    }
+}
+
+impl Default for ColorRange {
+    /// - `hue_start`: `0.0`
+    /// - `hue_end`: `1.0`
+    /// - `min_saturation`: `0.0`
+    /// - `max_saturation`: `1.0`
+    /// - `min_lightness`: `0.0`
+    /// - `max_lightness`: `1.0`
+    fn default() -> Self {
+        Self {
+            hue_start: 0.0,
+            hue_end: 1.0,
+            min_saturation: 0.0,
+            max_saturation: 1.0,
+            min_lightness: 0.0,
+            max_lightness: 1.0,
+        }
+    }
+}
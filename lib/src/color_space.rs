@@ -0,0 +1,151 @@
+use hsl::HSL;
+
+/// Color space the hue, saturation and brightness are converted from.
+///
+/// ```
+/// use sprite_gen::{generate, ColorSpace, MaskValue, Options};
+///
+/// let mask = vec![MaskValue::Body1; 8 * 8];
+/// let hsl = generate(&mask, 8, Options::default());
+/// let oklch = generate(
+///     &mask,
+///     8,
+///     Options {
+///         color_space: ColorSpace::Oklch,
+///         ..Default::default()
+///     },
+/// );
+///
+/// // Same shape with different colors
+/// assert_eq!(hsl.size(), oklch.size());
+/// assert_ne!(hsl, oklch);
+/// ```
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum ColorSpace {
+    /// HSL, the lightness is not perceptual so some hues look brighter than others.
+    #[default]
+    Hsl,
+    /// OKLCH, every hue looks equally bright at the same brightness.
+    ///
+    /// The saturation is scaled to the chroma and reduced when the color can't be displayed.
+    Oklch,
+}
+
+impl ColorSpace {
+    /// Convert a color with all components from `0.0` - `1.0` to `[r, g, b]`.
+    pub(crate) fn to_rgb(self, hue: f32, saturation: f32, brightness: f32) -> [u8; 3] {
+        match self {
+            ColorSpace::Hsl => {
+                let (r, g, b) = HSL {
+                    h: hue as f64 * 360.0,
+                    s: saturation as f64,
+                    l: brightness as f64,
+                }
+                .to_rgb();
+
+                [r, g, b]
+            }
+            ColorSpace::Oklch => oklch_to_rgb(hue, saturation, brightness),
+        }
+    }
+
+    /// Convert `[r, g, b]` to the hue, saturation and brightness, all from `0.0` - `1.0`.
+    pub(crate) fn components(self, rgb: [u8; 3]) -> (f32, f32, f32) {
+        match self {
+            ColorSpace::Hsl => {
+                let HSL { h, s, l } = HSL::from_rgb(&rgb);
+
+                (h as f32 / 360.0, s as f32, l as f32)
+            }
+            ColorSpace::Oklch => {
+                let [lightness, a, b] =
+                    linear_srgb_to_oklab(rgb.map(|channel| srgb_to_linear(channel as f32 / 255.0)));
+                let hue = b.atan2(a) / std::f32::consts::TAU;
+
+                (
+                    hue.rem_euclid(1.0),
+                    ((a * a + b * b).sqrt() / MAX_CHROMA).min(1.0),
+                    lightness,
+                )
+            }
+        }
+    }
+}
+
+/// Highest chroma that's used, most displayable colors are within it.
+const MAX_CHROMA: f32 = 0.25;
+
+/// Convert OKLCH to sRGB, reducing the chroma until the color fits.
+fn oklch_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [u8; 3] {
+    let lightness = lightness.clamp(0.0, 1.0);
+    let (sin, cos) = (hue * std::f32::consts::TAU).sin_cos();
+    let linear = |chroma: f32| oklab_to_linear_srgb(lightness, chroma * cos, chroma * sin);
+
+    let mut chroma = saturation.clamp(0.0, 1.0) * MAX_CHROMA;
+    if !in_gamut(linear(chroma)) {
+        // Binary search for the highest chroma that fits
+        let (mut low, mut high) = (0.0, chroma);
+        for _ in 0..16 {
+            let middle = (low + high) / 2.0;
+            if in_gamut(linear(middle)) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        chroma = low;
+    }
+
+    linear(chroma).map(|channel| (linear_to_srgb(channel) * 255.0).round() as u8)
+}
+
+/// Whether all channels can be displayed.
+fn in_gamut(rgb: [f32; 3]) -> bool {
+    rgb.iter()
+        .all(|channel| (-0.0001..=1.0001).contains(channel))
+}
+
+/// Convert OKLab to linear sRGB.
+fn oklab_to_linear_srgb(lightness: f32, a: f32, b: f32) -> [f32; 3] {
+    let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+
+    [
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    ]
+}
+
+/// Convert linear sRGB to OKLab.
+fn linear_srgb_to_oklab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+/// Remove the sRGB transfer function from a channel.
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.040_45 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Apply the sRGB transfer function to a linear channel.
+fn linear_to_srgb(channel: f32) -> f32 {
+    let channel = channel.clamp(0.0, 1.0);
+    if channel <= 0.003_130_8 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
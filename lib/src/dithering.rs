@@ -0,0 +1,144 @@
+use crate::{ColorMode, Options};
+
+/// Threshold pattern used for dithering.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum DitherPattern {
+    /// Ordered 2x2 Bayer matrix, a coarse pattern with few levels.
+    Bayer2,
+    /// Ordered 4x4 Bayer matrix, a finer pattern with more levels.
+    #[default]
+    Bayer4,
+    /// Alternating pixels like a checkerboard, only a single level in between.
+    Checker,
+}
+
+impl DitherPattern {
+    /// Threshold at a pixel from `-0.5` - `0.5`, averaging to `0.0` over the pattern.
+    fn threshold(self, x: usize, y: usize) -> f32 {
+        const BAYER2: [[u8; 2]; 2] = [[0, 2], [3, 1]];
+        const BAYER4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+        let (value, levels) = match self {
+            DitherPattern::Bayer2 => (BAYER2[y % 2][x % 2], 4),
+            DitherPattern::Bayer4 => (BAYER4[y % 4][x % 4], 16),
+            DitherPattern::Checker => (((x + y) % 2) as u8, 2),
+        };
+
+        (value as f32 + 0.5) / levels as f32 - 0.5
+    }
+}
+
+/// Ordered dithering of the brightness, so smooth gradients become patterns of pixels.
+///
+/// Applied once to the body of the full sprite, after the gradient and the shading but before the
+/// colors are matched to the palette, so palettes with a few shades still show the gradient.
+/// Without colors the body pixels are turned on or off based on the brightness of the gradient.
+///
+/// ```
+/// use sprite_gen::{
+///     generate, DitherPattern, Dithering, Gradient, MaskValue, Options, PixelFormat,
+/// };
+///
+/// let mask = vec![MaskValue::Body1; 8 * 8];
+/// let options = Options {
+///     colored: false,
+///     pixel_format: PixelFormat::Rgba,
+///     ..Default::default()
+/// };
+/// let plain = generate(&mask, 8, options.clone());
+/// let dithered = generate(
+///     &mask,
+///     8,
+///     Options {
+///         dithering: Dithering {
+///             pattern: DitherPattern::Checker,
+///             strength: 1.0,
+///         },
+///         ..options
+///     },
+/// );
+///
+/// // Some of the white body pixels are turned black
+/// let white = |pixels: &[u32]| pixels.iter().filter(|&&pixel| pixel == 0xFFFFFFFF).count();
+/// assert!(white(dithered.pixels()) < white(plain.pixels()));
+///
+/// // The pattern continues over the mirrored half
+/// let mask = vec![MaskValue::Detail; 4 * 4];
+/// let sprite = generate(
+///     &mask,
+///     4,
+///     Options {
+///         mirror_x: true,
+///         gradient: Gradient::Vertical,
+///         brightness_noise: 0.0,
+///         color_variations: 0.0,
+///         dithering: Dithering {
+///             pattern: DitherPattern::Checker,
+///             strength: 1.0,
+///         },
+///         ..Default::default()
+///     },
+/// );
+/// for row in sprite.rows() {
+///     assert!(row.windows(2).all(|pair| pair[0] != pair[1]));
+/// }
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Dithering {
+    /// Threshold pattern that's repeated over the sprite.
+    pub pattern: DitherPattern,
+    /// A value from `0.0` - `1.0`, how much the brightness is spread by the pattern.
+    ///
+    /// `0.0` disables the dithering.
+    pub strength: f32,
+}
+
+impl Dithering {
+    /// Whether the brightness is changed at all.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.strength > 0.0
+    }
+
+    /// How much the brightness of a pixel is changed, at full strength from `-0.25` - `0.25`.
+    fn offset(&self, x: usize, y: usize) -> f32 {
+        self.pattern.threshold(x, y) * self.strength * 0.5
+    }
+
+    /// Dither the body pixels of the full sprite and match them to the palette.
+    pub(crate) fn apply(
+        &self,
+        colors: &mut [[u8; 4]],
+        cells: &[i8],
+        width: usize,
+        options: &Options,
+    ) {
+        for (index, color) in colors.iter_mut().enumerate() {
+            if cells[index] <= 0 {
+                continue;
+            }
+
+            let offset = (self.offset(index % width, index / width) * 255.0) as i16;
+            let [r, g, b, a] = *color;
+            let mut rgb = [r, g, b].map(|channel| (channel as i16 + offset).clamp(0, 255) as u8);
+            if let ColorMode::Palette(palette) = &options.color_mode {
+                rgb = palette.quantize(rgb);
+            }
+
+            let [r, g, b] = rgb;
+            *color = [r, g, b, a];
+        }
+    }
+
+    /// Which body pixels of the full 1-bit sprite are turned off, from the brightness of the
+    /// gradient.
+    pub(crate) fn turned_off<'a>(
+        &'a self,
+        brightness: &'a [f32],
+        cells: &'a [i8],
+        width: usize,
+    ) -> impl Iterator<Item = bool> + 'a {
+        cells.iter().enumerate().map(move |(index, &cell)| {
+            cell > 0 && brightness[index] + self.offset(index % width, index / width) < 0.5
+        })
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt::{Display, Formatter};
+
+use crate::MaskValue;
+
+/// Biggest width or height a mask is allowed to have.
+pub const MAX_MASK_SIZE: usize = 4096;
+
+/// Reasons why a sprite can't be generated.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The width of the mask is `0`.
+    ZeroWidth,
+    /// The mask doesn't contain any cells.
+    EmptyMask,
+    /// The length of the mask buffer is not a multiple of the width.
+    RaggedMask {
+        /// Length of the mask buffer.
+        len: usize,
+        /// Width of the mask.
+        width: usize,
+    },
+    /// The width or height of the mask is bigger than [`MAX_MASK_SIZE`].
+    MaskTooLarge {
+        /// Width of the mask.
+        width: usize,
+        /// Height of the mask.
+        height: usize,
+    },
+    /// A mask value is not between `-1` and `7`.
+    InvalidMaskValue {
+        /// Position of the value in the mask buffer.
+        index: usize,
+        /// The invalid value.
+        value: i8,
+    },
+    /// A character in the text representation of a mask is not a mask value.
+    ///
+    /// ```
+    /// use sprite_gen::Mask;
+    ///
+    /// let error = "1x\n".parse::<Mask>().unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "character 'x' at 1:2 is not one of '#', '.', '1', '2', '*', 'a', 'e', 'm', 'd'"
+    /// );
+    /// ```
+    InvalidMaskChar {
+        /// Line of the character, starting at `1`.
+        line: usize,
+        /// Column of the character, starting at `1`.
+        column: usize,
+        /// The invalid character.
+        char: char,
+    },
+    /// A row in the text representation of a mask has a different width than the first row.
+    MaskRowLength {
+        /// Line of the row, starting at `1`.
+        line: usize,
+        /// Width of the row.
+        len: usize,
+        /// Width of the first row.
+        width: usize,
+    },
+    /// The palette of [`crate::ColorMode::Palette`] doesn't contain any colors.
+    EmptyPalette,
+    /// A line in a palette file can't be parsed.
+    InvalidPalette {
+        /// Line in the file, starting at `1`.
+        line: usize,
+        /// What's wrong with the line.
+        reason: &'static str,
+    },
+    /// The symmetry can't be used with the mask.
+    InvalidSymmetry {
+        /// Why the symmetry can't be used.
+        reason: &'static str,
+    },
+    /// The sampling settings can't be used.
+    InvalidSampling {
+        /// Why the settings can't be used.
+        reason: &'static str,
+    },
+    /// A cell of a [`crate::ProbabilityMask`] has a chance outside of `0.0` - `1.0`.
+    InvalidProbability {
+        /// Index of the cell in the buffer.
+        index: usize,
+    },
+    /// The fill and border images of a [`crate::ProbabilityMask`] don't have the same size.
+    ImageSizeMismatch {
+        /// Amount of pixels in the fill image.
+        fill: usize,
+        /// Amount of pixels in the border image.
+        border: usize,
+    },
+    /// The minimum of a color range is higher than the maximum.
+    InvalidColorRange {
+        /// Which part of the range is invalid.
+        reason: &'static str,
+    },
+    /// The outline is thicker than [`MAX_MASK_SIZE`].
+    OutlineTooThick {
+        /// The invalid thickness.
+        thickness: usize,
+    },
+    /// An option can't be used together with [`crate::Options::deterministic`].
+    NotDeterministic {
+        /// Name of the field in [`crate::Options`].
+        name: &'static str,
+    },
+    /// An option is not in its allowed range.
+    OptionOutOfRange {
+        /// Name of the field in [`crate::Options`].
+        name: &'static str,
+        /// The invalid value.
+        value: f32,
+    },
+    /// An option is NaN or infinite.
+    NonFiniteOption {
+        /// Name of the field in [`crate::Options`].
+        name: &'static str,
+        /// The invalid value.
+        value: f32,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ZeroWidth => write!(f, "mask width is zero"),
+            Error::EmptyMask => write!(f, "mask is empty"),
+            Error::RaggedMask { len, width } => write!(
+                f,
+                "mask buffer of length {len} can't be divided into rows of width {width}"
+            ),
+            Error::MaskTooLarge { width, height } => write!(
+                f,
+                "mask of {width}x{height} is larger than the maximum of {MAX_MASK_SIZE}x{MAX_MASK_SIZE}"
+            ),
+            Error::InvalidMaskValue { index, value } => write!(
+                f,
+                "mask value {value} at index {index} is not between -1 and 7"
+            ),
+            Error::InvalidMaskChar { line, column, char } => {
+                // Every value the text format knows
+                let chars = (-1..=7)
+                    .map(|value| format!("'{}'", MaskValue::from(value).char()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(
+                    f,
+                    "character '{char}' at {line}:{column} is not one of {chars}"
+                )
+            }
+            Error::MaskRowLength { line, len, width } => write!(
+                f,
+                "row at line {line} is {len} characters wide but the mask is {width} wide"
+            ),
+            Error::EmptyPalette => write!(f, "palette doesn't contain any colors"),
+            Error::InvalidPalette { line, reason } => {
+                write!(f, "invalid palette at line {line}: {reason}")
+            }
+            Error::InvalidSymmetry { reason } => write!(f, "invalid symmetry: {reason}"),
+            Error::InvalidSampling { reason } => write!(f, "invalid sampling: {reason}"),
+            Error::InvalidProbability { index } => write!(
+                f,
+                "cell probability at index {index} is not between 0.0 and 1.0"
+            ),
+            Error::ImageSizeMismatch { fill, border } => write!(
+                f,
+                "fill image has {fill} pixels but the border image has {border} pixels"
+            ),
+            Error::InvalidColorRange { reason } => write!(f, "invalid color range: {reason}"),
+            Error::OutlineTooThick { thickness } => write!(
+                f,
+                "outline thickness {thickness} is larger than the maximum of {MAX_MASK_SIZE}"
+            ),
+            Error::NotDeterministic { name } => write!(
+                f,
+                "option `{name}` can't be used when the output must be deterministic"
+            ),
+            Error::OptionOutOfRange { name, value } => {
+                write!(f, "option `{name}` is {value} but must be between 0.0 and 1.0")
+            }
+            Error::NonFiniteOption { name, value } => {
+                write!(f, "option `{name}` is {value} but must be a finite number")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
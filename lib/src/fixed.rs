@@ -0,0 +1,258 @@
+use randomize::PCG32;
+
+use crate::{
+    gradient::{self, GradientLayout},
+    ColorMode, ColorRange, HueBands, Material, Options, EMISSIVE_STREAM, HUE_STREAM, NOISE_STREAM,
+};
+
+/// `1.0` as a fixed-point number, all numbers have 16 fractional bits.
+pub(crate) const ONE: i32 = 1 << 16;
+
+/// Convert a float to a fixed-point number.
+///
+/// Scaling by a power of two and truncating is exact, so this is the same on every target.
+pub(crate) fn from_f32(value: f32) -> i32 {
+    (value * ONE as f32) as i32
+}
+
+/// Convert a fixed-point number to a float, which is exact for the range the colors use.
+pub(crate) fn to_f32(value: i32) -> f32 {
+    value as f32 / ONE as f32
+}
+
+/// Multiply two fixed-point numbers.
+fn mul(a: i32, b: i32) -> i32 {
+    ((a as i64 * b as i64) >> 16) as i32
+}
+
+/// A random number from `0.0` - `1.0`.
+fn random(rng: &mut PCG32) -> i32 {
+    (rng.next_u32() >> 16) as i32
+}
+
+/// Sine of an angle where `1.0` is a full turn.
+///
+/// Uses the approximation of Bhaskara I, which is off by less than `0.002`.
+pub(crate) fn sin(turns: i32) -> i32 {
+    let turns = turns.rem_euclid(ONE);
+    let (turns, sign) = if turns >= ONE / 2 {
+        (turns - ONE / 2, -1)
+    } else {
+        (turns, 1)
+    };
+
+    // The original formula scaled from radians to turns
+    let product = mul(turns, ONE / 2 - turns) as i64;
+    let sine = 64 * product * ONE as i64 / (5 * ONE as i64 - 16 * product);
+
+    sign * sine as i32
+}
+
+/// Cosine of an angle where `1.0` is a full turn.
+pub(crate) fn cos(turns: i32) -> i32 {
+    sin(turns + ONE / 4)
+}
+
+/// Color the sampled mask like the regular color output, but only with integer math.
+///
+/// Draws the same amount of random numbers from the same streams so the shape and the hues
+/// change the same way with the options.
+pub(crate) fn color_output(
+    mask: &[i8],
+    materials: &[Material],
+    mask_size: (usize, usize),
+    options: &Options,
+) -> Vec<[u8; 4]> {
+    let mut result = vec![[0x00, 0x00, 0x00, 0x00]; mask.len()];
+
+    let emissive_hue = random(&mut PCG32::seed(options.seed, EMISSIVE_STREAM));
+
+    let mut hue_rng = PCG32::seed(options.seed, HUE_STREAM);
+    let mut noise_rng = PCG32::seed(options.seed, NOISE_STREAM);
+
+    let is_vertical_gradient = hue_rng.next_u32() >= 1 << 31;
+    let range = &options.color_range;
+    let mut saturation = range_saturation(
+        range,
+        mul(random(&mut hue_rng), from_f32(options.saturation)),
+    );
+    let mut hue = range_hue(range, random(&mut hue_rng));
+    if let Some(base_color) = options.base_color {
+        (hue, saturation) = rgb_to_hue_saturation(base_color);
+    }
+
+    let variation_check = ONE - from_f32(options.color_variations);
+    let brightness_noise = from_f32(options.brightness_noise);
+    let edge_brightness = from_f32(options.edge_brightness);
+    let edges_separately = options.outline.colors_separately(options);
+
+    let gradient = GradientLayout::new(mask_size, options, is_vertical_gradient);
+
+    let (hue_keys, hue_key_count) = match options.hue_bands {
+        HueBands::Scanline => (gradient.bands.clone(), gradient.band_count),
+        HueBands::Region => gradient::regions(mask, mask_size),
+    };
+    let hues = (0..hue_key_count)
+        .map(|_| {
+            let is_new_color =
+                (random(&mut hue_rng) + random(&mut hue_rng) + random(&mut hue_rng)) / 3;
+
+            let new_hue = range_hue(range, random(&mut hue_rng));
+            if is_new_color > variation_check {
+                hue = new_hue;
+            }
+
+            hue
+        })
+        .collect::<Vec<_>>();
+
+    let mut order = (0..mask.len())
+        .filter(|&index| mask[index] != 0)
+        .collect::<Vec<_>>();
+    order.sort_by_key(|&index| (gradient.bands[index], index));
+
+    for index in order {
+        let val = mask[index];
+        let hue = hues[hue_keys[index]];
+        // The gradient is calculated with fixed-point numbers too, so this is exact
+        let gradient_brightness = from_f32(gradient.brightness[index]);
+
+        let noise = random(&mut noise_rng);
+        let brightness = range_lightness(
+            range,
+            mul(gradient_brightness, ONE - brightness_noise) + mul(noise, brightness_noise),
+        );
+
+        let material = if val > 0 {
+            materials[index]
+        } else {
+            Material::Body
+        };
+        let (hue, saturation, brightness) =
+            material_color(material, hue, saturation, brightness, noise, emissive_hue);
+        let mut rgb = hsl_to_rgb(hue, saturation, brightness);
+
+        let edge = rgb.map(|channel| mul(channel as i32, edge_brightness) as u8);
+        let is_edge = val == -1 && !edges_separately;
+
+        match &options.color_mode {
+            ColorMode::Free => {
+                if is_edge {
+                    rgb = edge;
+                }
+            }
+            ColorMode::Palette(_) if val > 0 && options.dithering.is_enabled() => (),
+            ColorMode::Palette(palette) => {
+                let mut palette_index = palette.nearest(rgb);
+                if is_edge {
+                    palette_index = palette.darker(palette_index, edge);
+                }
+
+                rgb = palette.colors()[palette_index];
+            }
+        }
+
+        let [r, g, b] = rgb;
+        result[index] = [r, g, b, 0xFF];
+    }
+
+    result
+}
+
+/// Same as [`Material::color`].
+fn material_color(
+    material: Material,
+    hue: i32,
+    saturation: i32,
+    brightness: i32,
+    noise: i32,
+    emissive_hue: i32,
+) -> (i32, i32, i32) {
+    match material {
+        Material::Body => (hue, saturation, brightness),
+        Material::Emissive => (emissive_hue, ONE, ONE * 3 / 5 + noise / 5),
+        Material::Accent => ((hue + ONE / 3) % ONE, saturation.max(ONE / 2), brightness),
+        Material::Eye => (
+            (hue + ONE / 2) % ONE,
+            ONE * 4 / 5,
+            (ONE - brightness).clamp(ONE / 4, ONE * 17 / 20),
+        ),
+        Material::Metal => (hue, saturation * 3 / 20, brightness),
+        Material::Detail => (hue, saturation, brightness / 2),
+    }
+}
+
+/// Same as [`ColorRange::hue`].
+fn range_hue(range: &ColorRange, value: i32) -> i32 {
+    let (start, end) = (from_f32(range.hue_start), from_f32(range.hue_end));
+    if end >= start {
+        start + mul(value, end - start)
+    } else {
+        (start + mul(value, end + ONE - start)) % ONE
+    }
+}
+
+/// Same as [`ColorRange::saturation`].
+fn range_saturation(range: &ColorRange, saturation: i32) -> i32 {
+    let (min, max) = (
+        from_f32(range.min_saturation),
+        from_f32(range.max_saturation),
+    );
+
+    min + mul(saturation, max - min)
+}
+
+/// Same as [`ColorRange::lightness`].
+fn range_lightness(range: &ColorRange, lightness: i32) -> i32 {
+    let (min, max) = (from_f32(range.min_lightness), from_f32(range.max_lightness));
+
+    min + mul(lightness, max - min)
+}
+
+/// Convert a color with all components from `0.0` - `1.0` to `[r, g, b]`.
+fn hsl_to_rgb(hue: i32, saturation: i32, lightness: i32) -> [u8; 3] {
+    let (saturation, lightness) = (saturation.clamp(0, ONE), lightness.clamp(0, ONE));
+
+    let chroma = mul(ONE - (2 * lightness - ONE).abs(), saturation);
+    let sector = hue.rem_euclid(ONE) * 6;
+    let x = mul(chroma, ONE - (sector % (2 * ONE) - ONE).abs());
+    let m = lightness - chroma / 2;
+
+    let (r, g, b) = match sector / ONE {
+        0 => (chroma, x, 0),
+        1 => (x, chroma, 0),
+        2 => (0, chroma, x),
+        3 => (0, x, chroma),
+        4 => (x, 0, chroma),
+        _ => (chroma, 0, x),
+    };
+
+    [r, g, b]
+        .map(|channel| ((channel + m) * 255 + ONE / 2).clamp(0, 255 * ONE) / ONE)
+        .map(|channel| channel as u8)
+}
+
+/// Hue and saturation of `[r, g, b]`, from `0.0` - `1.0`.
+fn rgb_to_hue_saturation(rgb: [u8; 3]) -> (i32, i32) {
+    let [r, g, b] = rgb.map(i32::from);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let delta = max - min;
+    if delta == 0 {
+        return (0, 0);
+    }
+
+    let saturation = if max + min > 255 {
+        delta * ONE / (510 - max - min)
+    } else {
+        delta * ONE / (max + min)
+    };
+    let sector = if max == r {
+        ((g - b) * ONE / delta).rem_euclid(6 * ONE)
+    } else if max == g {
+        (b - r) * ONE / delta + 2 * ONE
+    } else {
+        (r - g) * ONE / delta + 4 * ONE
+    };
+
+    (sector / 6, saturation)
+}
@@ -0,0 +1,238 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use crate::{fixed, symmetry, Options};
+
+/// Direction the brightness of the body changes in.
+///
+/// The hue can also change between the bands of the gradient, see [`HueBands::Scanline`].
+///
+/// ```
+/// use sprite_gen::{generate, Gradient, MaskValue, Options};
+///
+/// let mask = vec![MaskValue::Body1; 8 * 8];
+/// let options = |gradient| Options {
+///     mirror_x: true,
+///     gradient,
+///     ..Default::default()
+/// };
+///
+/// // A fixed direction gives every sprite the same lighting
+/// for gradient in [Gradient::Vertical, Gradient::Radial, Gradient::Angle(45.0)] {
+///     let sprite = generate(&mask, 8, options(gradient));
+///     assert_eq!(sprite.size(), (16, 8));
+/// }
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Gradient {
+    /// Randomly horizontal or vertical, picked with the seed.
+    #[default]
+    Random,
+    /// Brightness changes from left to right, dark at the sides and bright in the middle.
+    Horizontal,
+    /// Brightness changes from top to bottom, dark at the top and bottom and bright in the middle.
+    Vertical,
+    /// Bright in the center of the sprite and dark at the outside.
+    Radial,
+    /// Like [`Gradient::Horizontal`] but rotated clockwise by the angle in degrees.
+    Angle(f32),
+}
+
+/// Where the hue of the body can change, how often is set with
+/// [`Options::color_variations`].
+///
+/// ```
+/// use sprite_gen::{generate, HueBands, Mask, Options};
+///
+/// // Two separate parts that can each get their own hue
+/// let mask: Mask = "22.22\n22.22\n".parse()?;
+/// let sprite = generate(
+///     mask.cells(),
+///     mask.width(),
+///     Options {
+///         hue_bands: HueBands::Region,
+///         color_variations: 1.0,
+///         ..Default::default()
+///     },
+/// );
+/// assert_eq!(sprite.size(), (5, 2));
+/// # Ok::<(), sprite_gen::Error>(())
+/// ```
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum HueBands {
+    /// Between every band of the gradient.
+    #[default]
+    Scanline,
+    /// Between the parts of the body that are separated by edges.
+    Region,
+}
+
+/// Position of every cell of the mask in the gradient.
+pub(crate) struct GradientLayout {
+    /// Band of every cell, cells in a band are colored together.
+    pub(crate) bands: Vec<usize>,
+    /// Amount of bands.
+    pub(crate) band_count: usize,
+    /// Brightness of every cell from `0.0` - `1.0`, before the noise is added.
+    pub(crate) brightness: Vec<f32>,
+}
+
+impl GradientLayout {
+    /// Calculate the gradient, `is_vertical` is used for [`Gradient::Random`].
+    pub(crate) fn new(mask_size: (usize, usize), options: &Options, is_vertical: bool) -> Self {
+        let (width, height) = mask_size;
+        let positions = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+
+        // The band along an axis, the original gradient
+        let linear = |vertical: bool| {
+            let (bands, band_count) = if vertical {
+                (
+                    positions.clone().map(|(_, y)| y).collect::<Vec<_>>(),
+                    height,
+                )
+            } else {
+                (positions.clone().map(|(x, _)| x).collect(), width)
+            };
+            let brightness = bands
+                .iter()
+                .map(|&band| {
+                    if options.deterministic {
+                        let turns =
+                            (band as i64 * fixed::ONE as i64 / (2 * band_count as i64)) as i32;
+                        fixed::to_f32(fixed::sin(turns))
+                    } else {
+                        (band as f32 / band_count as f32 * PI).sin()
+                    }
+                })
+                .collect();
+
+            Self {
+                bands,
+                band_count,
+                brightness,
+            }
+        };
+
+        // Bands from a distance of every cell, the profile maps it from `0.0` - `1.0`
+        let from_distances = |distances: Vec<f32>, profile: fn(f32) -> f32| {
+            let max = distances.iter().copied().fold(0.0, f32::max).max(1.0);
+            let bands = distances
+                .iter()
+                .map(|&distance| distance as usize)
+                .collect::<Vec<_>>();
+
+            Self {
+                band_count: bands.iter().max().map_or(0, |&max| max + 1),
+                bands,
+                brightness: distances
+                    .iter()
+                    .map(|&distance| profile(distance / max))
+                    .collect(),
+            }
+        };
+
+        match options.gradient {
+            Gradient::Random => linear(is_vertical),
+            Gradient::Horizontal => linear(false),
+            Gradient::Vertical => linear(true),
+            Gradient::Radial => {
+                let (center_x, center_y) = symmetry::mask_center(mask_size, options);
+                let distances = positions
+                    .map(|(x, y)| {
+                        let dx = x as f32 + 0.5 - center_x;
+                        let dy = y as f32 + 0.5 - center_y;
+
+                        (dx * dx + dy * dy).sqrt()
+                    })
+                    .collect();
+
+                if options.deterministic {
+                    from_distances(distances, |t| {
+                        fixed::to_f32(fixed::cos(fixed::from_f32(t) / 4))
+                    })
+                } else {
+                    from_distances(distances, |t| (t * FRAC_PI_2).cos())
+                }
+            }
+            Gradient::Angle(angle) => {
+                let (sin, cos) = if options.deterministic {
+                    let turns = fixed::from_f32(angle.rem_euclid(360.0) / 360.0);
+                    (
+                        fixed::to_f32(fixed::sin(turns)),
+                        fixed::to_f32(fixed::cos(turns)),
+                    )
+                } else {
+                    angle.to_radians().sin_cos()
+                };
+                let project = |x: f32, y: f32| x * cos + y * sin;
+
+                // Start from the corner that's the furthest back
+                let start = [(0.0, 0.0), (width as f32, 0.0), (0.0, height as f32)]
+                    .into_iter()
+                    .chain([(width as f32, height as f32)])
+                    .map(|(x, y)| project(x, y))
+                    .fold(f32::INFINITY, f32::min);
+                let distances = positions
+                    .map(|(x, y)| project(x as f32 + 0.5, y as f32 + 0.5) - start)
+                    .collect();
+
+                if options.deterministic {
+                    from_distances(distances, |t| {
+                        fixed::to_f32(fixed::sin(fixed::from_f32(t) / 2))
+                    })
+                } else {
+                    from_distances(distances, |t| (t * PI).sin())
+                }
+            }
+        }
+    }
+}
+
+/// Which part of the body every cell belongs to, edges belong to a neighbouring part.
+///
+/// Returns the part of every cell and the amount of parts, empty cells are in part `0`.
+pub(crate) fn regions(mask: &[i8], (width, height): (usize, usize)) -> (Vec<usize>, usize) {
+    let neighbours = |index: usize| {
+        let (x, y) = (index % width, index / width);
+
+        [
+            (y > 0).then(|| index - width),
+            (y + 1 < height).then(|| index + width),
+            (x > 0).then(|| index - 1),
+            (x + 1 < width).then(|| index + 1),
+        ]
+        .into_iter()
+        .flatten()
+    };
+
+    let mut regions = vec![None; mask.len()];
+    let mut count = 0;
+    for start in 0..mask.len() {
+        if mask[start] <= 0 || regions[start].is_some() {
+            continue;
+        }
+
+        // Flood fill the body part
+        let mut stack = vec![start];
+        regions[start] = Some(count);
+        while let Some(index) = stack.pop() {
+            for neighbour in neighbours(index) {
+                if mask[neighbour] > 0 && regions[neighbour].is_none() {
+                    regions[neighbour] = Some(count);
+                    stack.push(neighbour);
+                }
+            }
+        }
+
+        count += 1;
+    }
+
+    let regions = (0..mask.len())
+        .map(|index| {
+            regions[index]
+                .or_else(|| neighbours(index).find_map(|neighbour| regions[neighbour]))
+                .unwrap_or(0)
+        })
+        .collect();
+
+    (regions, count.max(1))
+}
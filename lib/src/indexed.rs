@@ -0,0 +1,301 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{palette::luminance, Material, Palette, Render};
+
+/// Maximum amount of palette slots reserved for edge colors.
+const MAX_EDGE_COLORS: usize = 64;
+
+/// Amount of parts with their own range, the edges, every material and the highlights.
+const PARTS: usize = 8;
+
+/// Which palette indices of an [`IndexedSprite`] belong to which part of the sprite.
+///
+/// Index `0` is always transparent, the edges start at index `1` and every following range
+/// directly follows the previous one, in the order of the fields. Colors of different parts are
+/// never merged, parts that aren't in the sprite get an empty range. Within a range the colors are
+/// sorted from dark to light.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IndexLayout {
+    /// Indices of the edge colors.
+    pub edge: Range<u8>,
+    /// Indices of the colors of [`Material::Body`].
+    pub body: Range<u8>,
+    /// Indices of the colors of [`Material::Emissive`].
+    pub emissive: Range<u8>,
+    /// Indices of the colors of [`Material::Accent`].
+    pub accent: Range<u8>,
+    /// Indices of the colors of [`Material::Eye`].
+    pub eye: Range<u8>,
+    /// Indices of the colors of [`Material::Metal`].
+    pub metal: Range<u8>,
+    /// Indices of the colors of [`Material::Detail`].
+    pub detail: Range<u8>,
+    /// Indices of the body pixels brightened by [`crate::Shading::highlights`], of any material.
+    pub highlight: Range<u8>,
+}
+
+impl IndexLayout {
+    /// Indices of the colors of a material.
+    pub fn material(&self, material: Material) -> &Range<u8> {
+        match material {
+            Material::Body => &self.body,
+            Material::Emissive => &self.emissive,
+            Material::Accent => &self.accent,
+            Material::Eye => &self.eye,
+            Material::Metal => &self.metal,
+            Material::Detail => &self.detail,
+        }
+    }
+}
+
+/// A generated sprite as a palette with a buffer of indices into that palette.
+///
+/// ```
+/// use sprite_gen::{generate_indexed, Mask, MaskValue, Material, Options};
+///
+/// let mask = vec![
+///     MaskValue::Empty, MaskValue::Empty, MaskValue::Empty,
+///     MaskValue::Empty, MaskValue::Body2, MaskValue::Body1,
+///     MaskValue::Solid, MaskValue::Body1, MaskValue::Body1,
+/// ];
+/// let sprite = generate_indexed(&mask, 3, Options::default());
+/// let layout = sprite.layout();
+///
+/// // Empty cells are always transparent
+/// assert_eq!(sprite.get(0, 0), Some(0));
+/// // Solid cells are always edges
+/// assert!(layout.edge.contains(&sprite.get(0, 2).unwrap()));
+/// assert_eq!(layout.body.start, layout.edge.end);
+///
+/// // Every material gets its own range
+/// let mask: Mask = "1111\n1e1*\n".parse()?;
+/// let sprite = generate_indexed(mask.cells(), mask.width(), Options::default());
+/// let layout = sprite.layout();
+/// assert!(layout.eye.contains(&sprite.get(1, 1).unwrap()));
+/// assert!(layout
+///     .material(Material::Emissive)
+///     .contains(&sprite.get(3, 1).unwrap()));
+/// assert!(layout.metal.is_empty());
+/// # Ok::<(), sprite_gen::Error>(())
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IndexedSprite {
+    /// Width in pixels.
+    width: usize,
+    /// Height in pixels.
+    height: usize,
+    /// All colors, the first color is the transparent slot.
+    palette: Palette,
+    /// Which part of the palette belongs to which part of the sprite.
+    layout: IndexLayout,
+    /// All pixels as indices into the palette, row by row.
+    indices: Vec<u8>,
+}
+
+impl IndexedSprite {
+    /// Build the palette and the indices from the generated pixels.
+    pub(crate) fn from_render(render: &Render) -> Self {
+        // Which part every pixel belongs to, in the order of the ranges
+        let parts = (0..render.cells.len())
+            .map(|index| match render.cells[index] {
+                0 => None,
+                -1 => Some(0),
+                _ if render.highlights[index] => Some(PARTS - 1),
+                _ => Some(match render.materials[index] {
+                    Material::Body => 1,
+                    Material::Emissive => 2,
+                    Material::Accent => 3,
+                    Material::Eye => 4,
+                    Material::Metal => 5,
+                    Material::Detail => 6,
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        // Collect the colors of every part separately so they end up in their own ranges
+        let mut colors = vec![Vec::new(); PARTS];
+        for (&[r, g, b, _], part) in render.colors.iter().zip(&parts) {
+            if let Some(part) = *part {
+                colors[part].push([r, g, b]);
+            }
+        }
+        for part in &mut colors {
+            part.sort_unstable();
+            part.dedup();
+        }
+
+        // The end of the last range must also fit in an index, after the transparent slot
+        let edge_slots = colors[0].len().min(MAX_EDGE_COLORS);
+        let body_slots = budgets(
+            &colors[1..].iter().map(Vec::len).collect::<Vec<_>>(),
+            u8::MAX as usize - 1 - edge_slots,
+        );
+        let tones = colors
+            .into_iter()
+            .zip(std::iter::once(edge_slots).chain(body_slots))
+            .map(|(colors, max)| Tones::new(colors.into_iter(), max))
+            .collect::<Vec<_>>();
+
+        let mut starts = [1; PARTS];
+        for part in 1..PARTS {
+            starts[part] = starts[part - 1] + tones[part - 1].colors.len();
+        }
+        let [edge, body, emissive, accent, eye, metal, detail, highlight] =
+            std::array::from_fn(|part| {
+                starts[part] as u8..(starts[part] + tones[part].colors.len()) as u8
+            });
+        let layout = IndexLayout {
+            edge,
+            body,
+            emissive,
+            accent,
+            eye,
+            metal,
+            detail,
+            highlight,
+        };
+
+        let indices = render
+            .colors
+            .iter()
+            .zip(&parts)
+            .map(|(&[r, g, b, _], part)| match *part {
+                Some(part) => (starts[part] + tones[part].lookup[&[r, g, b]]) as u8,
+                None => 0,
+            })
+            .collect();
+
+        let palette = std::iter::once([0x00, 0x00, 0x00])
+            .chain(tones.into_iter().flat_map(|tones| tones.colors))
+            .collect();
+
+        Self {
+            width: render.width,
+            height: render.height,
+            palette,
+            layout,
+            indices,
+        }
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Width and height in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// All colors, the color at index `0` is unused because that index is transparent.
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Which part of the palette belongs to which part of the sprite.
+    pub fn layout(&self) -> &IndexLayout {
+        &self.layout
+    }
+
+    /// All pixels as indices into the palette, row by row.
+    pub fn indices(&self) -> &[u8] {
+        &self.indices
+    }
+
+    /// Take the indices, row by row.
+    pub fn into_indices(self) -> Vec<u8> {
+        self.indices
+    }
+
+    /// Get the index of a single pixel, `None` when the coordinates are outside of the sprite.
+    pub fn get(&self, x: usize, y: usize) -> Option<u8> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(self.indices[x + y * self.width])
+    }
+
+    /// Iterate over each row of indices.
+    pub fn rows(&self) -> std::slice::ChunksExact<'_, u8> {
+        // Chunks of zero are not allowed, an empty sprite has no pixels anyway
+        self.indices.chunks_exact(self.width.max(1))
+    }
+}
+
+/// Divide the slots over the parts, every part gets at most as many slots as it has colors.
+///
+/// Parts with fewer colors than an equal share leave their remaining slots to the other parts.
+fn budgets(needed: &[usize], slots: usize) -> Vec<usize> {
+    let mut budgets = vec![0; needed.len()];
+    let mut open = (0..needed.len())
+        .filter(|&part| needed[part] > 0)
+        .collect::<Vec<_>>();
+    let mut slots = slots;
+    while !open.is_empty() {
+        let share = slots / open.len();
+        let (fits, rest): (Vec<_>, Vec<_>) = open.iter().partition(|&&part| needed[part] <= share);
+        if fits.is_empty() {
+            for part in rest {
+                budgets[part] = share;
+            }
+
+            break;
+        }
+
+        for part in fits {
+            budgets[part] = needed[part];
+            slots -= needed[part];
+        }
+        open = rest;
+    }
+
+    budgets
+}
+
+/// A limited amount of colors sorted from dark to light.
+struct Tones {
+    /// The reduced colors.
+    colors: Vec<[u8; 3]>,
+    /// Which reduced color each original color maps to.
+    lookup: HashMap<[u8; 3], usize>,
+}
+
+impl Tones {
+    /// Reduce the colors to at most `max` tones by grouping them on brightness.
+    fn new<I>(colors: I, max: usize) -> Self
+    where
+        I: Iterator<Item = [u8; 3]>,
+    {
+        let mut unique = colors.collect::<Vec<_>>();
+        unique.sort_by_key(|&rgb| (luminance(rgb), rgb));
+        unique.dedup();
+
+        let amount = unique.len().min(max);
+        let mut sums = vec![([0u32; 3], 0u32); amount];
+        let mut lookup = HashMap::with_capacity(unique.len());
+        for (rank, &rgb) in unique.iter().enumerate() {
+            let tone = rank * amount / unique.len();
+            lookup.insert(rgb, tone);
+
+            let (sum, count) = &mut sums[tone];
+            for channel in 0..3 {
+                sum[channel] += rgb[channel] as u32;
+            }
+            *count += 1;
+        }
+
+        let colors = sums
+            .into_iter()
+            .map(|(sum, count)| sum.map(|channel| (channel / count) as u8))
+            .collect();
+
+        Self { colors, lookup }
+    }
+}
@@ -1,5 +1,54 @@
-use hsl::HSL;
+mod cleanup;
+mod color_range;
+mod color_space;
+mod dithering;
+mod error;
+mod fixed;
+mod gradient;
+mod indexed;
+mod light;
+mod mask;
+mod material;
+mod monochrome;
+mod outline;
+mod palette;
+mod probability;
+mod sampling;
+mod shading;
+mod sprite;
+mod symmetry;
+
+use gradient::GradientLayout;
 use randomize::{formulas, PCG32};
+use symmetry::SymmetryMap;
+
+pub use cleanup::{Cleanup, Connectivity};
+pub use color_range::ColorRange;
+pub use color_space::ColorSpace;
+pub use dithering::{DitherPattern, Dithering};
+pub use error::{Error, MAX_MASK_SIZE};
+pub use gradient::{Gradient, HueBands};
+pub use indexed::{IndexLayout, IndexedSprite};
+pub use light::Light;
+pub use mask::Mask;
+pub use material::Material;
+pub use monochrome::Monochrome;
+pub use outline::{EdgeColors, Outline, OutlinePlacement};
+pub use palette::{Palette, PaletteFormat};
+pub use probability::{CellProbability, ProbabilityMask};
+pub use sampling::Sampling;
+pub use shading::Shading;
+pub use sprite::{PixelFormat, Sprite};
+pub use symmetry::Symmetry;
+
+/// Random stream for sampling the shape from the mask.
+const SHAPE_STREAM: u64 = 5;
+/// Random stream for picking the gradient direction, the saturation and the hues.
+const HUE_STREAM: u64 = 6;
+/// Random stream for the brightness noise of every pixel.
+const NOISE_STREAM: u64 = 7;
+/// Random stream for the hue of the emissive pixels.
+const EMISSIVE_STREAM: u64 = 8;
 
 /// Replacement for the `i8` datatype that can be passed to `gen_sprite`.
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
@@ -13,6 +62,18 @@ pub enum MaskValue {
     Body1,
     /// - `2`: This pixel will either be a border or filled (body).
     Body2,
+    /// - `3`: This pixel will always be filled with a glowing color, see [`Material::Emissive`].
+    Emissive,
+    /// - `4`: This pixel will either be empty or filled with an accent color, see
+    ///   [`Material::Accent`].
+    Accent,
+    /// - `5`: This pixel will always be filled with a contrasting color, see [`Material::Eye`].
+    Eye,
+    /// - `6`: This pixel will either be empty or filled with a metallic color, see
+    ///   [`Material::Metal`].
+    Metal,
+    /// - `7`: This pixel will always be filled with a darker color, see [`Material::Detail`].
+    Detail,
 }
 
 impl MaskValue {
@@ -22,6 +83,63 @@ impl MaskValue {
             MaskValue::Empty => 0,
             MaskValue::Body1 => 1,
             MaskValue::Body2 => 2,
+            MaskValue::Emissive => 3,
+            MaskValue::Accent => 4,
+            MaskValue::Eye => 5,
+            MaskValue::Metal => 6,
+            MaskValue::Detail => 7,
+        }
+    }
+
+    /// Character used for this value in the text representation of a [`Mask`].
+    pub fn char(&self) -> char {
+        match self {
+            MaskValue::Solid => '#',
+            MaskValue::Empty => '.',
+            MaskValue::Body1 => '1',
+            MaskValue::Body2 => '2',
+            MaskValue::Emissive => '*',
+            MaskValue::Accent => 'a',
+            MaskValue::Eye => 'e',
+            MaskValue::Metal => 'm',
+            MaskValue::Detail => 'd',
+        }
+    }
+
+    /// Chances of this value turning into a body or a border, see [`CellProbability`].
+    pub fn probability(&self) -> CellProbability {
+        let (fill, border, material) = match self {
+            MaskValue::Solid => (0.0, 1.0, Material::Body),
+            MaskValue::Empty => (0.0, 0.0, Material::Body),
+            MaskValue::Body1 => (0.5, 0.0, Material::Body),
+            MaskValue::Body2 => (0.5, 1.0, Material::Body),
+            MaskValue::Emissive => (1.0, 0.0, Material::Emissive),
+            MaskValue::Accent => (0.5, 0.0, Material::Accent),
+            MaskValue::Eye => (1.0, 0.0, Material::Eye),
+            MaskValue::Metal => (0.5, 0.0, Material::Metal),
+            MaskValue::Detail => (1.0, 0.0, Material::Detail),
+        };
+
+        CellProbability {
+            fill,
+            border,
+            material,
+        }
+    }
+
+    /// Parse the character used in the text representation of a [`Mask`].
+    pub fn from_char(from: char) -> Option<Self> {
+        match from {
+            '#' => Some(MaskValue::Solid),
+            '.' => Some(MaskValue::Empty),
+            '1' => Some(MaskValue::Body1),
+            '2' => Some(MaskValue::Body2),
+            '*' => Some(MaskValue::Emissive),
+            'a' => Some(MaskValue::Accent),
+            'e' => Some(MaskValue::Eye),
+            'm' => Some(MaskValue::Metal),
+            'd' => Some(MaskValue::Detail),
+            _ => None,
         }
     }
 }
@@ -38,18 +156,103 @@ impl From<i8> for MaskValue {
             -1 => MaskValue::Solid,
             1 => MaskValue::Body1,
             2 => MaskValue::Body2,
+            3 => MaskValue::Emissive,
+            4 => MaskValue::Accent,
+            5 => MaskValue::Eye,
+            6 => MaskValue::Metal,
+            7 => MaskValue::Detail,
             _ => MaskValue::Empty,
         }
     }
 }
 
+/// How the colors of a sprite are picked.
+///
+/// ```
+/// use sprite_gen::{generate, ColorMode, MaskValue, Options, Palette, PixelFormat};
+///
+/// let palette = Palette::from_hex(&[0x222034, 0x45283C, 0xD95763, 0xFBF236]);
+/// let sprite = generate(
+///     &vec![MaskValue::Body2; 8 * 8],
+///     8,
+///     Options {
+///         color_mode: ColorMode::Palette(palette.clone()),
+///         pixel_format: PixelFormat::Argb,
+///         ..Default::default()
+///     },
+/// );
+/// for pixel in sprite.pixels() {
+///     let [_, r, g, b] = pixel.to_be_bytes();
+///     assert!(palette.colors().contains(&[r, g, b]));
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub enum ColorMode {
+    /// Any color can be picked.
+    #[default]
+    Free,
+    /// Colors are quantized to the closest color of the palette, edges use a darker color of the
+    /// palette.
+    Palette(Palette),
+}
+
 /// The options for the `gen_sprite` function.
-#[derive(Debug, Copy, Clone)]
+///
+/// Since version `0.3` this is no longer `Copy` because [`ColorMode::Palette`] owns its colors,
+/// use `.clone()` to reuse the options.
+#[derive(Debug, Clone)]
 pub struct Options {
     /// `true` if the result buffer should be mirrored along the X axis.
+    ///
+    /// Only used with [`Symmetry::Mirror`].
     pub mirror_x: bool,
     /// `true` if the result buffer should be mirrored along the Y axis.
+    ///
+    /// Only used with [`Symmetry::Mirror`].
     pub mirror_y: bool,
+    /// `true` if the last column and row of the mask are the mirror axes and are not duplicated.
+    ///
+    /// This gives mirrored sprites an odd size with a single center column or row, so a mask of
+    /// 6 wide results in a sprite of 11 wide.
+    /// Only used with [`Symmetry::Mirror`].
+    ///
+    /// ```
+    /// use sprite_gen::Options;
+    ///
+    /// let options = Options {
+    ///     mirror_x: true,
+    ///     mirror_center: true,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(options.sprite_size(6, 8), (11, 8));
+    /// ```
+    pub mirror_center: bool,
+    /// How the mask is repeated to build the full sprite.
+    pub symmetry: Symmetry,
+    /// How the cells of the mask are randomly filled.
+    pub sampling: Sampling,
+    /// How the randomly filled shape is cleaned up before the edges are added.
+    pub cleanup: Cleanup,
+    /// How the edges around the body are generated.
+    pub outline: Outline,
+    /// Light shining on the sprite, edges facing it are lighter and edges facing away darker.
+    pub light: Option<Light>,
+    /// Shading of the body based on the generated shape.
+    pub shading: Shading,
+    /// Ordered dithering of the gradient and the shading.
+    pub dithering: Dithering,
+    /// `true` if a normal map should be generated next to the colors, see [`Sprite::normal_map`].
+    pub normal_map: bool,
+    /// `true` if a map with only the glowing pixels should be generated next to the colors, see
+    /// [`Sprite::emissive_map`].
+    pub emissive_map: bool,
+    /// How the pixels of the result are packed.
+    pub pixel_format: PixelFormat,
+    /// Colors used when `colored` is `false`, also adds [`Sprite::to_1bpp`].
+    ///
+    /// `None` keeps the output where everything except the edges is white with
+    /// [`PixelFormat::Rgb`].
+    pub monochrome: Option<Monochrome>,
     /// `true` if the output should be colored. `false` if the output should be 1-bit. The
     /// Fields after this field only apply if `colored` is `true`.
     pub colored: bool,
@@ -61,33 +264,300 @@ pub struct Options {
     pub brightness_noise: f32,
     /// A value from `0.0` - `1.0`.
     pub saturation: f32,
+    /// Direction the brightness of the body changes in.
+    pub gradient: Gradient,
+    /// Where the hue of the body can change.
+    pub hue_bands: HueBands,
+    /// Color space the hue, saturation and brightness are converted from.
+    pub color_space: ColorSpace,
+    /// Limits for the hue, saturation and lightness of the body.
+    pub color_range: ColorRange,
+    /// Color `[r, g, b]` the body starts with instead of a random hue and saturation.
+    ///
+    /// The lightness still comes from the gradient, new hues from
+    /// [`Options::color_variations`] are picked from [`Options::color_range`].
+    ///
+    /// ```
+    /// use sprite_gen::{generate, MaskValue, Options};
+    ///
+    /// let mask = vec![MaskValue::Body1; 8 * 8];
+    /// let options = |seed| Options {
+    ///     base_color: Some([0x20, 0x80, 0x30]),
+    ///     color_variations: 0.0,
+    ///     brightness_noise: 0.0,
+    ///     seed,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// // Only the shape changes with the seed, not the color
+    /// let color = |seed| generate(&mask, 8, options(seed)).get(4, 4);
+    /// assert_eq!(color(1), color(2));
+    /// ```
+    pub base_color: Option<[u8; 3]>,
+    /// How the colors are picked.
+    pub color_mode: ColorMode,
+    /// Calculate the colors with integer math only, so the same mask and options result in
+    /// exactly the same pixels on every platform.
+    ///
+    /// The colors are slightly different from the regular output. Options that need floating
+    /// point functions can't be used, [`Options::validate`] fails with
+    /// [`Error::NotDeterministic`] for them:
+    /// - `color_space`: `ColorSpace::Oklch`
+    /// - `light`
+    /// - `shading`
+    /// - `symmetry`: `Symmetry::Radial`
+    ///
+    /// ```
+    /// use sprite_gen::{try_generate, Error, Light, MaskValue, Options};
+    ///
+    /// let mask = vec![MaskValue::Body1; 8 * 8];
+    /// let options = Options {
+    ///     deterministic: true,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(try_generate(&mask, 8, options.clone())?.size(), (8, 8));
+    ///
+    /// let options = Options {
+    ///     light: Some(Light {
+    ///         angle: 0.0,
+    ///         strength: 1.0,
+    ///     }),
+    ///     ..options
+    /// };
+    /// assert_eq!(
+    ///     options.validate(),
+    ///     Err(Error::NotDeterministic { name: "light" })
+    /// );
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub deterministic: bool,
     /// The seed for the random generator.
+    ///
+    /// The shape, the hues and the brightness noise each use their own random stream derived from
+    /// it, so changing the color options recolors the same shape.
+    ///
+    /// ```
+    /// use sprite_gen::{generate, MaskValue, Options, PixelFormat};
+    ///
+    /// let mask = vec![MaskValue::Body1; 16 * 16];
+    /// let options = Options {
+    ///     pixel_format: PixelFormat::Rgba,
+    ///     seed: 42,
+    ///     ..Default::default()
+    /// };
+    /// let shape = |options: Options| {
+    ///     generate(&mask, 16, options)
+    ///         .pixels()
+    ///         .iter()
+    ///         .map(|pixel| pixel & 0xFF)
+    ///         .collect::<Vec<_>>()
+    /// };
+    ///
+    /// assert_eq!(
+    ///     shape(options.clone()),
+    ///     shape(Options {
+    ///         saturation: 0.9,
+    ///         color_variations: 0.8,
+    ///         ..options
+    ///     })
+    /// );
+    /// ```
     pub seed: u64,
 }
 
 impl Default for Options {
     /// - `mirror_x`: `false`
     /// - `mirror_y`: `false`
+    /// - `mirror_center`: `false`
+    /// - `symmetry`: `Symmetry::Mirror`
+    /// - `sampling`: `Sampling::Independent`
+    /// - `cleanup`: `Cleanup::default()`, which doesn't change anything
+    /// - `outline`: `Outline::default()`, one pixel around the body
+    /// - `light`: `None`
+    /// - `shading`: `Shading::default()`, which doesn't change anything
+    /// - `dithering`: `Dithering::default()`, which doesn't change anything
+    /// - `normal_map`: `false`
+    /// - `emissive_map`: `false`
+    /// - `pixel_format`: `PixelFormat::Rgb`
+    /// - `monochrome`: `None`
     /// - `colored`: `true`
     /// - `edge_brightness`: `0.3`
     /// - `color_variations`: `0.2`
     /// - `brightness_noise`: `0.3`
     /// - `saturation`: `0.5`
+    /// - `gradient`: `Gradient::Random`
+    /// - `hue_bands`: `HueBands::Scanline`
+    /// - `color_space`: `ColorSpace::Hsl`
+    /// - `color_range`: `ColorRange::default()`, which allows every color
+    /// - `base_color`: `None`
+    /// - `color_mode`: `ColorMode::Free`
+    /// - `deterministic`: `false`
     /// - `seed`: `0`
     fn default() -> Self {
         Options {
             mirror_x: false,
             mirror_y: false,
+            mirror_center: false,
+            symmetry: Symmetry::Mirror,
+            sampling: Sampling::Independent,
+            cleanup: Cleanup::default(),
+            outline: Outline::default(),
+            light: None,
+            shading: Shading::default(),
+            dithering: Dithering::default(),
+            normal_map: false,
+            emissive_map: false,
+            pixel_format: PixelFormat::Rgb,
+            monochrome: None,
             colored: true,
             edge_brightness: 0.3,
             color_variations: 0.2,
             brightness_noise: 0.3,
             saturation: 0.5,
+            gradient: Gradient::Random,
+            hue_bands: HueBands::Scanline,
+            color_space: ColorSpace::Hsl,
+            color_range: ColorRange::default(),
+            base_color: None,
+            color_mode: ColorMode::Free,
+            deterministic: false,
             seed: 0,
         }
     }
 }
 
+impl Options {
+    /// Check whether all fields are within their allowed ranges.
+    ///
+    /// ```
+    /// use sprite_gen::{Error, Gradient, Options};
+    ///
+    /// assert!(Options::default().validate().is_ok());
+    ///
+    /// let options = Options {
+    ///     saturation: 1.5,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     options.validate(),
+    ///     Err(Error::OptionOutOfRange {
+    ///         name: "saturation",
+    ///         value: 1.5
+    ///     })
+    /// );
+    ///
+    /// let options = Options {
+    ///     gradient: Gradient::Angle(f32::INFINITY),
+    ///     ..Default::default()
+    /// };
+    /// assert!(matches!(
+    ///     options.validate(),
+    ///     Err(Error::NonFiniteOption {
+    ///         name: "gradient",
+    ///         ..
+    ///     })
+    /// ));
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        for (name, value) in [
+            ("edge_brightness", self.edge_brightness),
+            ("color_variations", self.color_variations),
+            ("brightness_noise", self.brightness_noise),
+            ("saturation", self.saturation),
+        ] {
+            // Also catches NaN
+            if !(0.0..=1.0).contains(&value) {
+                return Err(Error::OptionOutOfRange { name, value });
+            }
+        }
+
+        self.sampling.validate()?;
+        self.color_range.validate()?;
+
+        for (name, value) in self.shading.strengths() {
+            // Also catches NaN
+            if !(0.0..=1.0).contains(&value) {
+                return Err(Error::OptionOutOfRange { name, value });
+            }
+        }
+
+        if self.outline.thickness > MAX_MASK_SIZE {
+            return Err(Error::OutlineTooThick {
+                thickness: self.outline.thickness,
+            });
+        }
+
+        // Also catches NaN
+        if !(0.0..=1.0).contains(&self.dithering.strength) {
+            return Err(Error::OptionOutOfRange {
+                name: "dithering.strength",
+                value: self.dithering.strength,
+            });
+        }
+
+        if let Gradient::Angle(angle) = self.gradient {
+            if !angle.is_finite() {
+                return Err(Error::NonFiniteOption {
+                    name: "gradient",
+                    value: angle,
+                });
+            }
+        }
+
+        if let Some(light) = self.light {
+            if !(0.0..=1.0).contains(&light.strength) {
+                return Err(Error::OptionOutOfRange {
+                    name: "light.strength",
+                    value: light.strength,
+                });
+            }
+
+            if !light.angle.is_finite() {
+                return Err(Error::NonFiniteOption {
+                    name: "light.angle",
+                    value: light.angle,
+                });
+            }
+        }
+
+        if self.deterministic {
+            for (name, used) in [
+                ("color_space", self.color_space == ColorSpace::Oklch),
+                ("light", self.light.is_some()),
+                ("shading", self.shading.is_enabled()),
+                ("symmetry", matches!(self.symmetry, Symmetry::Radial(_))),
+            ] {
+                if used {
+                    return Err(Error::NotDeterministic { name });
+                }
+            }
+        }
+
+        if let ColorMode::Palette(palette) = &self.color_mode {
+            if palette.is_empty() {
+                return Err(Error::EmptyPalette);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Size of the generated sprite for a mask of this size.
+    ///
+    /// ```
+    /// use sprite_gen::Options;
+    ///
+    /// let options = Options {
+    ///     mirror_x: true,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(options.sprite_size(6, 8), (12, 8));
+    /// ```
+    pub fn sprite_size(&self, mask_width: usize, mask_height: usize) -> (usize, usize) {
+        symmetry::sprite_size((mask_width, mask_height), self)
+    }
+}
+
 /// Randomly generate a new sprite.
 ///
 /// A mask buffer of `i8` values should be passed together with the width of that buffer.
@@ -98,6 +568,13 @@ impl Default for Options {
 /// - `1`: This pixel will either be empty or filled (body).
 /// - `2`: This pixel will either be a border or filled (body).
 ///
+/// This is a thin wrapper around [`generate`] returning only the pixels, use that function
+/// when the size of the result is needed.
+///
+/// # Panics
+///
+/// When `mask_width` is `0`.
+///
 /// ```
 /// use sprite_gen::{gen_sprite, Options, MaskValue};
 ///
@@ -105,135 +582,483 @@ impl Default for Options {
 /// let buffer = gen_sprite(&mask, 12, Options::default());
 /// ```
 pub fn gen_sprite<T>(mask_buffer: &[T], mask_width: usize, options: Options) -> Vec<u32>
+where
+    T: Into<i8> + Clone,
+{
+    generate(mask_buffer, mask_width, options).into_pixels()
+}
+
+/// Randomly generate a new sprite, returning it with its final size.
+///
+/// See [`gen_sprite`] for the meaning of the mask values, cells after the last full row are
+/// dropped.
+///
+/// # Panics
+///
+/// When `mask_width` is `0`, use [`try_generate`] to get an error instead.
+///
+/// ```
+/// use sprite_gen::{generate, Options, MaskValue};
+///
+/// let mask = vec![MaskValue::Body1; 6 * 4];
+/// let sprite = generate(
+///     &mask,
+///     6,
+///     Options {
+///         mirror_x: true,
+///         mirror_y: true,
+///         ..Default::default()
+///     },
+/// );
+/// assert_eq!(sprite.size(), (12, 8));
+/// assert_eq!(sprite.pixels().len(), 12 * 8);
+/// ```
+pub fn generate<T>(mask_buffer: &[T], mask_width: usize, options: Options) -> Sprite
 where
     T: Into<i8> + Clone,
 {
     let mask_height = mask_buffer.len() / mask_width;
 
-    // Copy the array to this vector
-    let mut mask: Vec<i8> = mask_buffer
+    // Copy the array to this vector, cells after the last full row are dropped
+    let mask: Vec<i8> = mask_buffer
         .iter()
+        .take(mask_width * mask_height)
         .map(|v| std::convert::Into::into(v.clone()))
         .collect::<_>();
 
-    let mut rng = PCG32::seed(options.seed, 5);
-
-    // Generate a random sample, if it's a internal body there is a 50% chance it will be empty
-    // If it's a regular body there is a 50% chance it will turn into a border
-    for val in mask.iter_mut() {
-        if *val == 1 {
-            // Either 0 or 1
-            *val = formulas::f32_closed(rng.next_u32()).round() as i8;
-        } else if *val == 2 {
-            // Either -1 or 1
-            *val = formulas::f32_closed_neg_pos(rng.next_u32()).signum() as i8;
-        }
+    generate_unchecked(mask, mask_width, mask_height, options)
+}
+
+/// Randomly generate a new sprite, failing when the mask or the options are invalid.
+///
+/// This is the fallible version of [`generate`], which never panics or silently drops mask
+/// cells.
+///
+/// ```
+/// use sprite_gen::{try_generate, Error, Options, MaskValue};
+///
+/// let mask = vec![MaskValue::Body1; 12 * 8];
+/// let sprite = try_generate(&mask, 12, Options::default())?;
+/// assert_eq!(sprite.size(), (12, 8));
+///
+/// assert_eq!(
+///     try_generate(&mask, 0, Options::default()),
+///     Err(Error::ZeroWidth)
+/// );
+/// assert_eq!(
+///     try_generate(&mask, 7, Options::default()),
+///     Err(Error::RaggedMask { len: 96, width: 7 })
+/// );
+/// # Ok::<(), Error>(())
+/// ```
+pub fn try_generate<T>(
+    mask_buffer: &[T],
+    mask_width: usize,
+    options: Options,
+) -> Result<Sprite, Error>
+where
+    T: Into<i8> + Clone,
+{
+    options.validate()?;
+    let (mask, mask_height) = validate_mask(mask_buffer, mask_width)?;
+    options.symmetry.validate(mask_width, mask_height)?;
+
+    Ok(generate_unchecked(mask, mask_width, mask_height, options))
+}
+
+/// Randomly generate a new sprite as a palette with indices into that palette.
+///
+/// Index `0` is transparent, see [`IndexLayout`] for which indices belong to the edges and
+/// which to the materials of the body.
+/// See [`gen_sprite`] for the meaning of the mask values.
+///
+/// ```
+/// use sprite_gen::{generate_indexed, MaskValue, Options};
+///
+/// let mask = vec![MaskValue::Body2; 4 * 4];
+/// let sprite = generate_indexed(
+///     &mask,
+///     4,
+///     Options {
+///         mirror_x: true,
+///         ..Default::default()
+///     },
+/// );
+/// assert_eq!(sprite.size(), (8, 4));
+/// assert!(sprite
+///     .indices()
+///     .iter()
+///     .all(|&index| (index as usize) < sprite.palette().len()));
+/// ```
+pub fn generate_indexed<T>(mask_buffer: &[T], mask_width: usize, options: Options) -> IndexedSprite
+where
+    T: Into<i8> + Clone,
+{
+    let mask_height = mask_buffer.len() / mask_width;
+
+    // Copy the array to this vector, cells after the last full row are dropped
+    let mask: Vec<i8> = mask_buffer
+        .iter()
+        .take(mask_width * mask_height)
+        .map(|v| std::convert::Into::into(v.clone()))
+        .collect::<_>();
+
+    IndexedSprite::from_render(&render(
+        &probabilities(&mask),
+        (mask_width, mask_height),
+        &options,
+    ))
+}
+
+/// Randomly generate a new indexed sprite, failing when the mask or the options are invalid.
+///
+/// This is the fallible version of [`generate_indexed`].
+pub fn try_generate_indexed<T>(
+    mask_buffer: &[T],
+    mask_width: usize,
+    options: Options,
+) -> Result<IndexedSprite, Error>
+where
+    T: Into<i8> + Clone,
+{
+    options.validate()?;
+    let (mask, mask_height) = validate_mask(mask_buffer, mask_width)?;
+    options.symmetry.validate(mask_width, mask_height)?;
+
+    Ok(IndexedSprite::from_render(&render(
+        &probabilities(&mask),
+        (mask_width, mask_height),
+        &options,
+    )))
+}
+
+/// Randomly generate a new sprite from a mask where every cell has its own chances.
+///
+/// See [`ProbabilityMask`] for how the chances are used.
+pub fn generate_probabilities(mask: &ProbabilityMask, options: Options) -> Sprite {
+    sprite_from_render(render(mask.cells(), mask.size(), &options), &options)
+}
+
+/// Randomly generate a new sprite from a mask where every cell has its own chances, failing when
+/// the options are invalid.
+///
+/// This is the fallible version of [`generate_probabilities`].
+pub fn try_generate_probabilities(
+    mask: &ProbabilityMask,
+    options: Options,
+) -> Result<Sprite, Error> {
+    options.validate()?;
+    if mask.cells().is_empty() {
+        return Err(Error::EmptyMask);
     }
+    if mask.width() > MAX_MASK_SIZE || mask.height() > MAX_MASK_SIZE {
+        return Err(Error::MaskTooLarge {
+            width: mask.width(),
+            height: mask.height(),
+        });
+    }
+    // Also catches cells that were changed after constructing the mask
+    if let Some(index) = mask.cells().iter().position(|cell| !cell.is_valid()) {
+        return Err(Error::InvalidProbability { index });
+    }
+    options.symmetry.validate(mask.width(), mask.height())?;
 
-    // Generate edges
-    for y in 0..mask_height {
-        for x in 0..mask_width {
-            let index = x + y * mask_width;
-            if mask[index] <= 0 {
-                continue;
-            }
+    Ok(generate_probabilities(mask, options))
+}
 
-            if y > 0 && mask[index - mask_width] == 0 {
-                mask[index - mask_width] = -1;
-            }
-            if y < mask_height - 1 && mask[index + mask_width] == 0 {
-                mask[index + mask_width] = -1;
-            }
-            if x > 0 && mask[index - 1] == 0 {
-                mask[index - 1] = -1;
-            }
-            if x < mask_width - 1 && mask[index + 1] == 0 {
-                mask[index + 1] = -1;
-            }
-        }
+/// Check whether the mask can be used for generating, returning it as `i8` values and its height.
+fn validate_mask<T>(mask_buffer: &[T], mask_width: usize) -> Result<(Vec<i8>, usize), Error>
+where
+    T: Into<i8> + Clone,
+{
+    if mask_width == 0 {
+        return Err(Error::ZeroWidth);
+    }
+    if mask_buffer.is_empty() {
+        return Err(Error::EmptyMask);
+    }
+    if !mask_buffer.len().is_multiple_of(mask_width) {
+        return Err(Error::RaggedMask {
+            len: mask_buffer.len(),
+            width: mask_width,
+        });
     }
 
-    // Color the mask image
-    let colored: Vec<u32> = if options.colored {
-        color_output(&mask, (mask_width, mask_height), &options, &mut rng)
+    let mask_height = mask_buffer.len() / mask_width;
+    if mask_width > MAX_MASK_SIZE || mask_height > MAX_MASK_SIZE {
+        return Err(Error::MaskTooLarge {
+            width: mask_width,
+            height: mask_height,
+        });
+    }
+
+    let mask = mask_buffer
+        .iter()
+        .enumerate()
+        .map(|(index, v)| {
+            let value = std::convert::Into::into(v.clone());
+            if (-1..=7).contains(&value) {
+                Ok(value)
+            } else {
+                Err(Error::InvalidMaskValue { index, value })
+            }
+        })
+        .collect::<Result<Vec<i8>, _>>()?;
+
+    Ok((mask, mask_height))
+}
+
+/// Generate the sprite from a mask that's known to be valid.
+fn generate_unchecked(
+    mask: Vec<i8>,
+    mask_width: usize,
+    mask_height: usize,
+    options: Options,
+) -> Sprite {
+    let render = render(&probabilities(&mask), (mask_width, mask_height), &options);
+
+    sprite_from_render(render, &options)
+}
+
+/// Chances of every cell of a regular mask.
+fn probabilities(mask: &[i8]) -> Vec<CellProbability> {
+    mask.iter()
+        .map(|&value| MaskValue::from(value).probability())
+        .collect()
+}
+
+/// Pack the colors of the render into a sprite.
+fn sprite_from_render(render: Render, options: &Options) -> Sprite {
+    let format = options.pixel_format;
+    let pixels = if !options.colored && options.monochrome.is_none() && format == PixelFormat::Rgb {
+        // Keep the old output where everything except the edges is white
+        let edge = Monochrome::default().outline();
+        render
+            .colors
+            .iter()
+            .map(|&color| if color == edge { 0 } else { 0xFF_FF_FF_FF })
+            .collect()
     } else {
-        onebit_output(&mask)
+        render
+            .colors
+            .iter()
+            .map(|&color| format.pack(color))
+            .collect()
     };
 
-    // Check for mirroring
-    if options.mirror_x && options.mirror_y {
-        // Mirror both X & Y
-        let width = mask_width * 2;
-        let height = mask_height * 2;
-        let mut result = vec![0; width * height];
+    let mut sprite = Sprite::new(render.width, render.height, format, pixels);
 
-        for y in 0..mask_height {
-            for x in 0..mask_width {
-                let index = x + y * mask_width;
-                let value = colored[index];
+    if options.normal_map {
+        let normals = shading::normal_map(&render.cells, (render.width, render.height))
+            .into_iter()
+            .map(|normal| format.pack(normal))
+            .collect();
+        sprite = sprite.with_normal_map(normals);
+    }
 
-                let index = x + y * width;
-                result[index] = value;
+    if options.emissive_map {
+        let emissive = render
+            .colors
+            .iter()
+            .zip(&render.cells)
+            .zip(&render.materials)
+            .map(|((&color, &cell), &material)| match material {
+                Material::Emissive if cell > 0 => format.pack(color),
+                _ => format.pack([0x00, 0x00, 0x00, 0x00]),
+            })
+            .collect();
+        sprite = sprite.with_emissive_map(emissive);
+    }
 
-                let index = (width - x - 1) + y * width;
-                result[index] = value;
+    if !options.colored && options.monochrome.is_some() {
+        let on = render
+            .cells
+            .iter()
+            .zip(&render.turned_off)
+            .map(|(&cell, &turned_off)| cell > 0 && !turned_off)
+            .collect::<Vec<_>>();
+        sprite = sprite.with_bits(monochrome::pack_bits(&on, render.width));
+    }
 
-                let index = x + (height - y - 1) * width;
-                result[index] = value;
+    sprite
+}
 
-                let index = (width - x - 1) + (height - y - 1) * width;
-                result[index] = value;
-            }
-        }
+/// Colors and cells of a generated sprite before they are packed.
+pub(crate) struct Render {
+    /// Width in pixels.
+    pub(crate) width: usize,
+    /// Height in pixels.
+    pub(crate) height: usize,
+    /// Every pixel as `[r, g, b, a]`.
+    pub(crate) colors: Vec<[u8; 4]>,
+    /// Every pixel as `-1` for edges, `0` for empty and `1` for body.
+    pub(crate) cells: Vec<i8>,
+    /// What every pixel is made of, only used for body pixels.
+    pub(crate) materials: Vec<Material>,
+    /// Body pixels turned off by the dithering of the 1-bit output.
+    pub(crate) turned_off: Vec<bool>,
+    /// Body pixels brightened by the highlights of the shading.
+    pub(crate) highlights: Vec<bool>,
+}
 
-        return result;
-    } else if options.mirror_x {
-        // Only mirror X
-        let width = mask_width * 2;
-        let mut result = vec![0; width * mask_height];
+/// Sample the mask, color it and apply the symmetry.
+fn render(cells: &[CellProbability], mask_size: (usize, usize), options: &Options) -> Render {
+    let (mask_width, mask_height) = mask_size;
 
-        for y in 0..mask_height {
-            for x in 0..mask_width {
-                let index = x + y * mask_width;
-                let value = colored[index];
+    // Every stage gets its own random stream so changing the colors doesn't change the shape
+    let mut rng = PCG32::seed(options.seed, SHAPE_STREAM);
 
-                let index = x + y * width;
-                result[index] = value;
+    // Generate a random sample, every cell has a chance to become a body and otherwise a chance to
+    // become a border
+    // Only draw random numbers for uncertain outcomes, so the regular mask values always result
+    // in the same shapes
+    let mut mask = cells
+        .iter()
+        .enumerate()
+        .map(|(index, cell)| {
+            let is_body = match cell.fill {
+                fill if fill >= 1.0 => true,
+                fill if fill <= 0.0 => false,
+                fill => {
+                    let sample = match options.sampling {
+                        Sampling::Independent => formulas::f32_closed(rng.next_u32()),
+                        Sampling::Noise {
+                            frequency,
+                            threshold,
+                        } => {
+                            let noise = sampling::value_noise(
+                                options.seed,
+                                index % mask_width,
+                                index / mask_width,
+                                frequency,
+                            );
+
+                            // Shift the noise so the threshold lines up with the middle
+                            (noise - threshold + 0.5).clamp(0.0, 1.0)
+                        }
+                    };
 
-                let index = (width - x - 1) + y * width;
-                result[index] = value;
+                    sample >= 1.0 - fill
+                }
+            };
+            if is_body {
+                return 1;
             }
-        }
 
-        return result;
-    } else if options.mirror_y {
-        // Only mirror Y
-        let height = mask_height * 2;
-        let mut result = vec![0; mask_width * height];
+            let is_border = match cell.border {
+                border if border >= 1.0 => true,
+                border if border <= 0.0 => false,
+                border => formulas::f32_closed(rng.next_u32()) < border,
+            };
+            if is_border {
+                -1
+            } else {
+                0
+            }
+        })
+        .collect::<Vec<i8>>();
+
+    let symmetry = SymmetryMap::new((mask_width, mask_height), options);
+
+    if options.cleanup.is_enabled() {
+        options.cleanup.apply(&mut mask, cells, &symmetry);
+    }
+
+    // Generate edges, in the mask they get colored together with the body
+    let sampled = mask.clone();
+    options.outline.apply(&mut mask, (mask_width, mask_height));
 
-        for y in 0..mask_height {
-            for x in 0..mask_width {
-                let index = x + y * mask_width;
-                let value = colored[index];
-                result[index] = value;
+    // Color the mask image
+    let materials = cells.iter().map(|cell| cell.material).collect::<Vec<_>>();
+    let colors = if options.colored && options.deterministic {
+        fixed::color_output(&mask, &materials, (mask_width, mask_height), options)
+    } else if options.colored {
+        color_output(&mask, &materials, (mask_width, mask_height), options)
+    } else {
+        onebit_output(&mask, options)
+    };
+
+    let empty = if options.colored {
+        [0x00, 0x00, 0x00, 0x00]
+    } else {
+        options.monochrome.unwrap_or_default().background()
+    };
+    let mut colors = symmetry.apply(&colors, empty);
+    let materials = symmetry.apply(&materials, Material::Body);
 
-                let index = x + (height - y - 1) * mask_width;
-                result[index] = value;
+    // The symmetry can put cells next to each other that are far apart in the mask, so the
+    // outline of the full sprite is generated again
+    let size = (symmetry.width, symmetry.height);
+    let mut cells = symmetry.apply(&sampled, 0);
+    options.outline.apply(&mut cells, size);
+    options.outline.repair_seams(
+        &mut colors,
+        &mut cells,
+        &symmetry.apply(&mask, 0),
+        empty,
+        size,
+        options,
+    );
+    let mut turned_off = vec![false; cells.len()];
+    if !options.colored && options.dithering.is_enabled() {
+        // Same direction as the colored sprite would get
+        let mut hue_rng = PCG32::seed(options.seed, HUE_STREAM);
+        let is_vertical_gradient = formulas::f32_closed_neg_pos(hue_rng.next_u32()) > 0.0;
+        let gradient = GradientLayout::new(mask_size, options, is_vertical_gradient);
+        let brightness = symmetry.apply(&gradient.brightness, 0.0);
+
+        turned_off = options
+            .dithering
+            .turned_off(&brightness, &cells, size.0)
+            .collect();
+
+        let outline = options.monochrome.unwrap_or_default().outline();
+        for (color, &off) in colors.iter_mut().zip(&turned_off) {
+            if off {
+                *color = outline;
             }
         }
+    }
+
+    let mut highlights = vec![false; cells.len()];
+    if options.colored && options.shading.is_enabled() {
+        highlights = options
+            .shading
+            .apply(&mut colors, &cells, &materials, size, options);
+    }
+
+    // Dither the final sprite so the pattern lines up over the mirrored parts
+    if options.colored && options.dithering.is_enabled() {
+        options
+            .dithering
+            .apply(&mut colors, &cells, size.0, options);
+    }
 
-        return result;
+    // Edges depending on their surroundings can only be colored when the full sprite is known
+    if options.colored && options.outline.colors_separately(options) {
+        options.outline.color(&mut colors, &cells, size, options);
     }
 
-    colored
+    Render {
+        width: symmetry.width,
+        height: symmetry.height,
+        colors,
+        cells,
+        materials,
+        turned_off,
+        highlights,
+    }
 }
 
 #[inline]
-fn onebit_output(mask: &[i8]) -> Vec<u32> {
+fn onebit_output(mask: &[i8], options: &Options) -> Vec<[u8; 4]> {
+    let monochrome = options.monochrome.unwrap_or_default();
+
     mask.iter()
         .map(|&v| match v {
-            -1 => 0,
-            _ => 0xFF_FF_FF_FF,
+            -1 => monochrome.outline(),
+            0 => monochrome.background(),
+            _ => monochrome.foreground(),
         })
         .collect()
 }
@@ -241,69 +1066,104 @@ fn onebit_output(mask: &[i8]) -> Vec<u32> {
 #[inline]
 fn color_output(
     mask: &[i8],
+    materials: &[Material],
     mask_size: (usize, usize),
     options: &Options,
-    rng: &mut PCG32,
-) -> Vec<u32> {
-    let mut result = vec![0xFF_FF_FF_FF; mask.len()];
+) -> Vec<[u8; 4]> {
+    let mut result = vec![[0x00, 0x00, 0x00, 0x00]; mask.len()];
 
-    let is_vertical_gradient = formulas::f32_closed_neg_pos(rng.next_u32()) > 0.0;
-    let saturation = formulas::f32_closed(rng.next_u32()) * options.saturation;
-    let mut hue = formulas::f32_closed(rng.next_u32());
+    // All glowing pixels share a hue, picked separately so they don't change the other colors
+    let emissive_hue = formulas::f32_closed(PCG32::seed(options.seed, EMISSIVE_STREAM).next_u32());
+
+    let mut hue_rng = PCG32::seed(options.seed, HUE_STREAM);
+    let mut noise_rng = PCG32::seed(options.seed, NOISE_STREAM);
+
+    let is_vertical_gradient = formulas::f32_closed_neg_pos(hue_rng.next_u32()) > 0.0;
+    let range = &options.color_range;
+    let mut saturation =
+        range.saturation(formulas::f32_closed(hue_rng.next_u32()) * options.saturation);
+    let mut hue = range.hue(formulas::f32_closed(hue_rng.next_u32()));
+    if let Some(base_color) = options.base_color {
+        (hue, saturation, _) = options.color_space.components(base_color);
+    }
 
     let variation_check = 1.0 - options.color_variations;
     let brightness_inv = 1.0 - options.brightness_noise;
+    let edges_separately = options.outline.colors_separately(options);
 
-    let uv_size = if is_vertical_gradient {
-        (mask_size.1, mask_size.0)
-    } else {
-        mask_size
+    let gradient = GradientLayout::new(mask_size, options, is_vertical_gradient);
+
+    // Which cells can change hue together
+    let (hue_keys, hue_key_count) = match options.hue_bands {
+        HueBands::Scanline => (gradient.bands.clone(), gradient.band_count),
+        HueBands::Region => gradient::regions(mask, mask_size),
     };
+    let hues = (0..hue_key_count)
+        .map(|_| {
+            // Create a non-uniform random number being constrained more to the center (0)
+            let is_new_color = (formulas::f32_closed(hue_rng.next_u32())
+                + formulas::f32_closed(hue_rng.next_u32())
+                + formulas::f32_closed(hue_rng.next_u32()))
+                / 3.0;
 
-    for u in 0..uv_size.0 {
-        // Create a non-uniform random number being constrained more to the center (0)
-        let is_new_color = (formulas::f32_closed(rng.next_u32())
-            + formulas::f32_closed(rng.next_u32())
-            + formulas::f32_closed(rng.next_u32()))
-            / 3.0;
+            // Always draw the new hue so the amount of variations doesn't shift the stream
+            let new_hue = range.hue(formulas::f32_closed(hue_rng.next_u32()));
+            if is_new_color > variation_check {
+                hue = new_hue;
+            }
 
-        if is_new_color > variation_check {
-            hue = formulas::f32_closed(rng.next_u32());
-        }
+            hue
+        })
+        .collect::<Vec<_>>();
 
-        let u_sin = ((u as f32 / uv_size.0 as f32) * std::f32::consts::PI).sin();
+    // Walk through the bands in order so the noise is the same for the same gradient
+    let mut order = (0..mask.len())
+        .filter(|&index| mask[index] != 0)
+        .collect::<Vec<_>>();
+    order.sort_by_key(|&index| (gradient.bands[index], index));
 
-        for v in 0..uv_size.1 {
-            let index = if is_vertical_gradient {
-                v + u * mask_size.0
-            } else {
-                u + v * mask_size.0
-            };
+    for index in order {
+        let val = mask[index];
+        let hue = hues[hue_keys[index]];
+        let u_sin = gradient.brightness[index];
 
-            let val = mask[index];
-            if val == 0 {
-                continue;
-            }
+        let noise = formulas::f32_closed(noise_rng.next_u32());
+        let brightness = range.lightness(u_sin * brightness_inv + noise * options.brightness_noise);
 
-            let brightness = u_sin * brightness_inv
-                + formulas::f32_closed(rng.next_u32()) * options.brightness_noise;
+        // Edges always get the color of the regular body
+        let material = if val > 0 {
+            materials[index]
+        } else {
+            Material::Body
+        };
+        let (hue, saturation, brightness) =
+            material.color(hue, saturation, brightness, noise, emissive_hue);
+        let mut rgb = options.color_space.to_rgb(hue, saturation, brightness);
 
-            let mut rgb = HSL {
-                h: hue as f64 * 360.0,
-                s: saturation as f64,
-                l: brightness as f64,
-            }
-            .to_rgb();
+        // Make the edges darker, unless they are colored later
+        let edge = rgb.map(|channel| (channel as f32 * options.edge_brightness) as u8);
+        let is_edge = val == -1 && !edges_separately;
 
-            // Make the edges darker
-            if val == -1 {
-                rgb.0 = (rgb.0 as f32 * options.edge_brightness) as u8;
-                rgb.1 = (rgb.1 as f32 * options.edge_brightness) as u8;
-                rgb.2 = (rgb.2 as f32 * options.edge_brightness) as u8;
+        match &options.color_mode {
+            ColorMode::Free => {
+                if is_edge {
+                    rgb = edge;
+                }
             }
+            // The body is matched to the palette after the dithering
+            ColorMode::Palette(_) if val > 0 && options.dithering.is_enabled() => (),
+            ColorMode::Palette(palette) => {
+                let mut palette_index = palette.nearest(rgb);
+                if is_edge {
+                    palette_index = palette.darker(palette_index, edge);
+                }
 
-            result[index] = ((rgb.0 as u32) << 16) | ((rgb.1 as u32) << 8) | (rgb.2 as u32);
+                rgb = palette.colors()[palette_index];
+            }
         }
+
+        let [r, g, b] = rgb;
+        result[index] = [r, g, b, 0xFF];
     }
 
     result
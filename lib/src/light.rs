@@ -0,0 +1,77 @@
+/// A directional light shining on the sprite.
+///
+/// ```
+/// use sprite_gen::{
+///     generate_probabilities, CellProbability, EdgeColors, Light, Options, Outline, PixelFormat,
+///     ProbabilityMask,
+/// };
+///
+/// // A single body pixel in the center
+/// let mut mask = ProbabilityMask::new(3, 3);
+/// mask.set(1, 1, CellProbability { fill: 1.0, ..Default::default() });
+/// let sprite = generate_probabilities(
+///     &mask,
+///     Options {
+///         outline: Outline {
+///             colors: EdgeColors::Selective,
+///             ..Default::default()
+///         },
+///         // Light from the top
+///         light: Some(Light {
+///             angle: 0.0,
+///             strength: 1.0,
+///         }),
+///         pixel_format: PixelFormat::Rgba,
+///         ..Default::default()
+///     },
+/// );
+///
+/// let brightness = |x, y| {
+///     let [r, g, b, _] = sprite.get(x, y).unwrap().to_be_bytes();
+///     r as u32 + g as u32 + b as u32
+/// };
+/// assert!(brightness(1, 0) > brightness(1, 2));
+///
+/// // The angle must be a finite number
+/// let options = Options {
+///     light: Some(Light {
+///         angle: f32::NAN,
+///         strength: 1.0,
+///     }),
+///     ..Default::default()
+/// };
+/// assert!(options.validate().is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Light {
+    /// Direction the light is coming from in degrees, clockwise with `0.0` being the top.
+    ///
+    /// Must be finite.
+    pub angle: f32,
+    /// A value from `0.0` - `1.0`, how much the light changes the colors.
+    pub strength: f32,
+}
+
+impl Light {
+    /// Unit vector pointing from the sprite towards the light, where `y` points down.
+    pub(crate) fn direction(&self) -> [f32; 2] {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+
+        [sin, -cos]
+    }
+
+    /// Brightness factor of a surface facing the direction of the normal.
+    ///
+    /// Returns `base` for surfaces perpendicular to the light, towards `1.0` when facing it and
+    /// towards `0.0` when facing away.
+    pub(crate) fn brightness(&self, base: f32, normal: [f32; 2]) -> f32 {
+        let [x, y] = self.direction();
+        let facing = (normal[0] * x + normal[1] * y) * self.strength;
+
+        if facing >= 0.0 {
+            base + (1.0 - base) * facing
+        } else {
+            base * (1.0 + facing)
+        }
+    }
+}
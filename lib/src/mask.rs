@@ -0,0 +1,375 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{Error, MaskValue};
+
+/// A grid of [`MaskValue`] cells describing the shape of a sprite.
+///
+/// Can be converted from and to a text representation where every row is a line and every
+/// cell is a character:
+/// - `#`: [`MaskValue::Solid`]
+/// - `.`: [`MaskValue::Empty`]
+/// - `1`: [`MaskValue::Body1`]
+/// - `2`: [`MaskValue::Body2`]
+/// - `*`: [`MaskValue::Emissive`]
+/// - `a`: [`MaskValue::Accent`]
+/// - `e`: [`MaskValue::Eye`]
+/// - `m`: [`MaskValue::Metal`]
+/// - `d`: [`MaskValue::Detail`]
+///
+/// ```
+/// use sprite_gen::{generate, Mask, Options};
+///
+/// let mask: Mask = "
+/// ..##
+/// .#11
+/// #122
+/// "
+/// .parse()?;
+/// assert_eq!(mask.size(), (4, 3));
+/// assert_eq!(mask.to_string(), "..##\n.#11\n#122\n");
+///
+/// let sprite = generate(mask.cells(), mask.width(), Options::default());
+/// assert_eq!(sprite.size(), (4, 3));
+/// # Ok::<(), sprite_gen::Error>(())
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Mask {
+    /// Amount of cells in a row.
+    width: usize,
+    /// Amount of rows.
+    height: usize,
+    /// All cells, row by row.
+    cells: Vec<MaskValue>,
+}
+
+impl Mask {
+    /// Construct a mask with all cells set to [`MaskValue::Empty`].
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::filled(width, height, MaskValue::Empty)
+    }
+
+    /// Construct a mask with all cells set to the same value.
+    pub fn filled(width: usize, height: usize, value: MaskValue) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// Construct a mask from a buffer of cells, row by row.
+    ///
+    /// ```
+    /// use sprite_gen::{Error, Mask, MaskValue};
+    ///
+    /// let mask = Mask::from_cells(vec![MaskValue::Body1; 6], 3)?;
+    /// assert_eq!(mask.size(), (3, 2));
+    ///
+    /// assert_eq!(
+    ///     Mask::from_cells(vec![MaskValue::Body1; 6], 4),
+    ///     Err(Error::RaggedMask { len: 6, width: 4 })
+    /// );
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn from_cells(cells: Vec<MaskValue>, width: usize) -> Result<Self, Error> {
+        if width == 0 {
+            return Err(Error::ZeroWidth);
+        }
+        if !cells.len().is_multiple_of(width) {
+            return Err(Error::RaggedMask {
+                len: cells.len(),
+                width,
+            });
+        }
+
+        Ok(Self {
+            width,
+            height: cells.len() / width,
+            cells,
+        })
+    }
+
+    /// Amount of cells in a row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Amount of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Width and height in cells.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// All cells, row by row.
+    pub fn cells(&self) -> &[MaskValue] {
+        &self.cells
+    }
+
+    /// Take the cells, row by row.
+    pub fn into_cells(self) -> Vec<MaskValue> {
+        self.cells
+    }
+
+    /// Get a single cell, `None` when the coordinates are outside of the mask.
+    pub fn get(&self, x: usize, y: usize) -> Option<&MaskValue> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.cells.get(x + y * self.width)
+    }
+
+    /// Set a single cell, does nothing when the coordinates are outside of the mask.
+    pub fn set(&mut self, x: usize, y: usize, value: MaskValue) {
+        if x < self.width && y < self.height {
+            self.cells[x + y * self.width] = value;
+        }
+    }
+
+    /// Mirror the mask horizontally, so the left side becomes the right side.
+    pub fn flip_x(&self) -> Self {
+        self.map_positions(self.width, self.height, |x, y| (self.width - x - 1, y))
+    }
+
+    /// Mirror the mask vertically, so the top side becomes the bottom side.
+    pub fn flip_y(&self) -> Self {
+        self.map_positions(self.width, self.height, |x, y| (x, self.height - y - 1))
+    }
+
+    /// Rotate the mask by 90° clockwise, swapping the width and the height.
+    ///
+    /// ```
+    /// use sprite_gen::Mask;
+    ///
+    /// let mask: Mask = "#..\n111\n".parse()?;
+    /// assert_eq!(mask.rotate_90().to_string(), "1#\n1.\n1.\n");
+    /// # Ok::<(), sprite_gen::Error>(())
+    /// ```
+    pub fn rotate_90(&self) -> Self {
+        self.map_positions(self.height, self.width, |x, y| (y, self.height - x - 1))
+    }
+
+    /// Take a rectangular part of the mask.
+    ///
+    /// The rectangle is clamped to the size of the mask.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let width = width.min(self.width - x);
+        let height = height.min(self.height - y);
+
+        self.map_positions(width, height, |cell_x, cell_y| (cell_x + x, cell_y + y))
+    }
+
+    /// Add rows and columns of [`MaskValue::Empty`] cells around the mask.
+    ///
+    /// ```
+    /// use sprite_gen::Mask;
+    ///
+    /// let mask: Mask = "1\n".parse()?;
+    /// assert_eq!(mask.pad(1, 0, 2, 1).to_string(), ".1..\n....\n");
+    /// # Ok::<(), sprite_gen::Error>(())
+    /// ```
+    pub fn pad(&self, left: usize, top: usize, right: usize, bottom: usize) -> Self {
+        let mut result = Self::new(self.width + left + right, self.height + top + bottom);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                result.set(x + left, y + top, self.cells[x + y * self.width].clone());
+            }
+        }
+
+        result
+    }
+
+    /// Scale the mask to a new size using nearest-neighbour sampling.
+    ///
+    /// A mask without any cells results in a mask with only [`MaskValue::Empty`] cells.
+    ///
+    /// ```
+    /// use sprite_gen::Mask;
+    ///
+    /// let mask: Mask = "#1\n".parse()?;
+    /// assert_eq!(mask.resize(4, 2).to_string(), "##11\n##11\n");
+    /// # Ok::<(), sprite_gen::Error>(())
+    /// ```
+    pub fn resize(&self, width: usize, height: usize) -> Self {
+        // There's nothing to sample from
+        if self.width == 0 || self.height == 0 {
+            return Self::new(width, height);
+        }
+
+        self.map_positions(width, height, |x, y| {
+            (x * self.width / width, y * self.height / height)
+        })
+    }
+
+    /// Combine two masks, keeping the most filled cell of both.
+    ///
+    /// Cells are ranked from least to most filled as [`MaskValue::Empty`],
+    /// [`MaskValue::Body1`], [`MaskValue::Body2`], [`MaskValue::Emissive`] and
+    /// [`MaskValue::Solid`]. The other regions rank the same as the value they are filled like,
+    /// when both cells rank the same the cell of this mask is kept.
+    /// The result is big enough to hold both masks.
+    ///
+    /// ```
+    /// use sprite_gen::Mask;
+    ///
+    /// let a: Mask = "1.\n..\n".parse()?;
+    /// let b: Mask = ".2\n".parse()?;
+    /// assert_eq!(a.union(&b).to_string(), "12\n..\n");
+    /// # Ok::<(), sprite_gen::Error>(())
+    /// ```
+    pub fn union(&self, other: &Self) -> Self {
+        let width = self.width.max(other.width);
+        let height = self.height.max(other.height);
+
+        self.combine(other, width, height, |a, b| {
+            if fill_rank(&a) >= fill_rank(&b) {
+                a
+            } else {
+                b
+            }
+        })
+    }
+
+    /// Combine two masks, keeping the least filled cell of both.
+    ///
+    /// Uses the same ranking as [`Mask::union`].
+    /// The result only covers the area both masks share.
+    ///
+    /// ```
+    /// use sprite_gen::Mask;
+    ///
+    /// let a: Mask = "#1\n12\n".parse()?;
+    /// let b: Mask = "2#\n".parse()?;
+    /// assert_eq!(a.intersect(&b).to_string(), "21\n");
+    /// # Ok::<(), sprite_gen::Error>(())
+    /// ```
+    pub fn intersect(&self, other: &Self) -> Self {
+        let width = self.width.min(other.width);
+        let height = self.height.min(other.height);
+
+        self.combine(other, width, height, |a, b| {
+            if fill_rank(&a) <= fill_rank(&b) {
+                a
+            } else {
+                b
+            }
+        })
+    }
+
+    /// Build a new mask where every cell is copied from a position in this mask.
+    fn map_positions<F>(&self, width: usize, height: usize, position: F) -> Self
+    where
+        F: Fn(usize, usize) -> (usize, usize),
+    {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let (x, y) = position(x, y);
+                self.cells[x + y * self.width].clone()
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Build a new mask by combining cells of both masks, cells outside of a mask are empty.
+    fn combine<F>(&self, other: &Self, width: usize, height: usize, pick: F) -> Self
+    where
+        F: Fn(MaskValue, MaskValue) -> MaskValue,
+    {
+        let mut result = Self::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let a = self.get(x, y).cloned().unwrap_or_default();
+                let b = other.get(x, y).cloned().unwrap_or_default();
+                result.set(x, y, pick(a, b));
+            }
+        }
+
+        result
+    }
+}
+
+impl Display for Mask {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in self.cells.chunks_exact(self.width.max(1)) {
+            for cell in row {
+                write!(f, "{}", cell.char())?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Mask {
+    type Err = Error;
+
+    /// Parse the text representation, leading and trailing empty lines are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut width = None;
+        let mut cells = Vec::new();
+
+        let lines = s
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .skip_while(|(_, line)| line.is_empty())
+            .collect::<Vec<_>>();
+        let last = lines
+            .iter()
+            .rposition(|(_, line)| !line.is_empty())
+            .map_or(0, |index| index + 1);
+
+        for (index, line) in &lines[..last] {
+            let line_number = index + 1;
+            let row_width = line.chars().count();
+            let width = *width.get_or_insert(row_width);
+            if row_width != width {
+                return Err(Error::MaskRowLength {
+                    line: line_number,
+                    len: row_width,
+                    width,
+                });
+            }
+
+            for (column, char) in line.chars().enumerate() {
+                cells.push(MaskValue::from_char(char).ok_or(Error::InvalidMaskChar {
+                    line: line_number,
+                    column: column + 1,
+                    char,
+                })?);
+            }
+        }
+
+        match width {
+            Some(width) if width > 0 => Mask::from_cells(cells, width),
+            _ => Err(Error::EmptyMask),
+        }
+    }
+}
+
+/// How filled a cell will be after generation, used to combine masks.
+fn fill_rank(value: &MaskValue) -> u8 {
+    match value {
+        MaskValue::Empty => 0,
+        MaskValue::Body1 | MaskValue::Accent | MaskValue::Metal => 1,
+        MaskValue::Body2 => 2,
+        MaskValue::Emissive | MaskValue::Eye | MaskValue::Detail => 3,
+        MaskValue::Solid => 4,
+    }
+}
@@ -0,0 +1,62 @@
+/// What a filled cell is made of, decides how it's colored.
+///
+/// ```
+/// use sprite_gen::{generate, Mask, MaskValue, Material, Options};
+///
+/// let mask: Mask = "mmaa\n11ee\n1ddd\n".parse()?;
+/// assert_eq!(mask.get(2, 1), Some(&MaskValue::Eye));
+/// assert_eq!(MaskValue::Eye.probability().material, Material::Eye);
+/// assert_eq!(MaskValue::from(5), MaskValue::Eye);
+///
+/// // Eyes are always filled, empty pixels are `0xFFFFFFFF`
+/// let sprite = generate(mask.cells(), mask.width(), Options::default());
+/// assert_ne!(sprite.get(2, 1), Some(0xFFFFFFFF));
+/// # Ok::<(), sprite_gen::Error>(())
+/// ```
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Material {
+    /// Regular body colored with the gradient and the brightness noise.
+    #[default]
+    Body,
+    /// Glowing with a bright and saturated color, not affected by shading.
+    ///
+    /// These pixels are also part of the emissive map, see [`crate::Sprite::emissive_map`].
+    Emissive,
+    /// The body with the hue shifted by a third, for stripes and markings.
+    Accent,
+    /// A contrasting color with the opposite hue and brightness of the body, for eyes and
+    /// cockpits.
+    Eye,
+    /// The body with most of the saturation removed.
+    Metal,
+    /// The body at half the brightness, for small features.
+    Detail,
+}
+
+impl Material {
+    /// Hue, saturation and brightness of a body pixel made of this material.
+    ///
+    /// All values are from `0.0` - `1.0`, the hue, saturation and brightness are the colors the
+    /// regular body would get.
+    pub(crate) fn color(
+        self,
+        hue: f32,
+        saturation: f32,
+        brightness: f32,
+        noise: f32,
+        emissive_hue: f32,
+    ) -> (f32, f32, f32) {
+        match self {
+            Material::Body => (hue, saturation, brightness),
+            Material::Emissive => (emissive_hue, 1.0, 0.6 + noise * 0.2),
+            Material::Accent => ((hue + 1.0 / 3.0).fract(), saturation.max(0.5), brightness),
+            Material::Eye => (
+                (hue + 0.5).fract(),
+                0.8,
+                (1.0 - brightness).clamp(0.25, 0.85),
+            ),
+            Material::Metal => (hue, saturation * 0.15, brightness),
+            Material::Detail => (hue, saturation, brightness * 0.5),
+        }
+    }
+}
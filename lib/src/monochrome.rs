@@ -0,0 +1,89 @@
+/// Colors of the sprite when [`crate::Options::colored`] is `false`.
+///
+/// Body pixels get the foreground color, unless they are turned off by
+/// [`crate::Options::dithering`], then they get the outline color.
+///
+/// ```
+/// use sprite_gen::{generate, Mask, Monochrome, Options, PixelFormat};
+///
+/// let mask: Mask = ".#d\n".parse()?;
+/// let sprite = generate(
+///     mask.cells(),
+///     mask.width(),
+///     Options {
+///         colored: false,
+///         monochrome: Some(Monochrome {
+///             foreground: [0x9B, 0xBC, 0x0F],
+///             outline: [0x0F, 0x38, 0x0F],
+///             background: Some([0x8B, 0xAC, 0x0F]),
+///         }),
+///         pixel_format: PixelFormat::Rgba,
+///         ..Default::default()
+///     },
+/// );
+/// assert_eq!(sprite.pixels(), [0x8BAC0FFF, 0x0F380FFF, 0x9BBC0FFF]);
+/// # Ok::<(), sprite_gen::Error>(())
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Monochrome {
+    /// Color `[r, g, b]` of the body.
+    pub foreground: [u8; 3],
+    /// Color `[r, g, b]` of the edges.
+    pub outline: [u8; 3],
+    /// Color `[r, g, b]` of the empty pixels, `None` keeps them transparent.
+    pub background: Option<[u8; 3]>,
+}
+
+impl Monochrome {
+    /// Color of the body.
+    pub(crate) fn foreground(&self) -> [u8; 4] {
+        let [r, g, b] = self.foreground;
+
+        [r, g, b, 0xFF]
+    }
+
+    /// Color of the edges and the body pixels that are turned off.
+    pub(crate) fn outline(&self) -> [u8; 4] {
+        let [r, g, b] = self.outline;
+
+        [r, g, b, 0xFF]
+    }
+
+    /// Color of the empty pixels.
+    pub(crate) fn background(&self) -> [u8; 4] {
+        match self.background {
+            Some([r, g, b]) => [r, g, b, 0xFF],
+            None => [0x00, 0x00, 0x00, 0x00],
+        }
+    }
+}
+
+impl Default for Monochrome {
+    /// - `foreground`: white
+    /// - `outline`: black
+    /// - `background`: `None`
+    fn default() -> Self {
+        Self {
+            foreground: [0xFF, 0xFF, 0xFF],
+            outline: [0x00, 0x00, 0x00],
+            background: None,
+        }
+    }
+}
+
+/// Pack pixels to a bit each, row by row with the first pixel in the highest bit.
+///
+/// Every row starts at a new byte.
+pub(crate) fn pack_bits(on: &[bool], width: usize) -> Vec<u8> {
+    on.chunks(width.max(1))
+        .flat_map(|row| {
+            row.chunks(8).map(|pixels| {
+                pixels
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &on)| on)
+                    .fold(0, |byte, (bit, _)| byte | (0x80 >> bit))
+            })
+        })
+        .collect()
+}
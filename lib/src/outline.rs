@@ -0,0 +1,395 @@
+use crate::{ColorMode, Connectivity, Options};
+
+/// Where the outline is drawn.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum OutlinePlacement {
+    /// Around the body, the sprite grows.
+    #[default]
+    Outside,
+    /// On the border of the body, the sprite keeps the same size.
+    Inside,
+}
+
+/// How the colors of the edges are picked.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum EdgeColors {
+    /// The color of the body at the position of the edge.
+    #[default]
+    Own,
+    /// The colors of the adjacent body pixels, also called selective outlining.
+    Selective,
+}
+
+/// How the edges around the body are generated.
+///
+/// Borders from [`crate::MaskValue::Solid`] and [`crate::MaskValue::Body2`] cells are always kept,
+/// even when the outline is disabled.
+///
+/// ```
+/// use sprite_gen::{
+///     generate, generate_probabilities, CellProbability, Connectivity, Error, Mask, Options,
+///     Outline, OutlinePlacement, PixelFormat, ProbabilityMask, Symmetry,
+/// };
+///
+/// // A single body pixel in the center
+/// let mut mask = ProbabilityMask::new(5, 5);
+/// mask.set(2, 2, CellProbability { fill: 1.0, ..Default::default() });
+/// let edges = |outline| {
+///     let options = Options {
+///         colored: false,
+///         outline,
+///         ..Default::default()
+///     };
+///
+///     // Edges are black in the 1-bit output
+///     generate_probabilities(&mask, options)
+///         .pixels()
+///         .iter()
+///         .filter(|&&pixel| pixel == 0)
+///         .count()
+/// };
+///
+/// assert_eq!(edges(Outline::default()), 4);
+/// assert_eq!(edges(Outline::NONE), 0);
+/// assert_eq!(
+///     edges(Outline {
+///         connectivity: Connectivity::Eight,
+///         ..Default::default()
+///     }),
+///     8
+/// );
+/// assert_eq!(
+///     edges(Outline {
+///         thickness: 2,
+///         ..Default::default()
+///     }),
+///     12
+/// );
+/// assert_eq!(
+///     edges(Outline {
+///         placement: OutlinePlacement::Inside,
+///         ..Default::default()
+///     }),
+///     1
+/// );
+///
+/// // The outline is generated after the symmetry, so no body pixel ends up next to an empty one
+/// let mask: Mask = "...d\n.d..\n....\nd..d\n".parse()?;
+/// for symmetry in [
+///     Symmetry::Mirror,
+///     Symmetry::Rotate2,
+///     Symmetry::Rotate4,
+///     Symmetry::Diagonal,
+///     Symmetry::Radial(5),
+/// ] {
+///     let sprite = generate(
+///         mask.cells(),
+///         mask.width(),
+///         Options {
+///             symmetry,
+///             colored: false,
+///             pixel_format: PixelFormat::Rgba,
+///             ..Default::default()
+///         },
+///     );
+///     let (width, height) = sprite.size();
+///
+///     for (x, y) in (0..height).flat_map(|y| (0..width).map(move |x| (x, y))) {
+///         if sprite.get(x, y) != Some(0xFFFFFFFF) {
+///             continue;
+///         }
+///
+///         // White body pixels, black edges and transparent empty pixels
+///         for (x, y) in [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)] {
+///             assert_ne!(sprite.get(x, y), Some(0x00000000), "{symmetry:?} at ({x}, {y})");
+///         }
+///     }
+/// }
+///
+/// let options = Options {
+///     outline: Outline {
+///         thickness: usize::MAX,
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     options.validate(),
+///     Err(Error::OutlineTooThick {
+///         thickness: usize::MAX
+///     })
+/// );
+/// # Ok::<(), sprite_gen::Error>(())
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Outline {
+    /// Width of the outline in pixels, `0` disables it.
+    ///
+    /// Can't be larger than [`crate::MAX_MASK_SIZE`].
+    pub thickness: usize,
+    /// With [`Connectivity::Eight`] diagonal neighbours are outlined as well.
+    pub connectivity: Connectivity,
+    /// Where the outline is drawn.
+    pub placement: OutlinePlacement,
+    /// How the colors of the edges are picked, they are darkened with
+    /// [`Options::edge_brightness`].
+    pub colors: EdgeColors,
+}
+
+impl Outline {
+    /// Don't generate any edges.
+    pub const NONE: Self = Self {
+        thickness: 0,
+        connectivity: Connectivity::Four,
+        placement: OutlinePlacement::Outside,
+        colors: EdgeColors::Own,
+    };
+
+    /// Add the edges to the sampled mask, where `-1` is a border, `0` empty and `1` a body.
+    pub(crate) fn apply(&self, mask: &mut [i8], (width, height): (usize, usize)) {
+        let grows_into = |cell: i8| match self.placement {
+            OutlinePlacement::Outside => cell == 0,
+            OutlinePlacement::Inside => cell > 0,
+        };
+
+        // Grow the outline a single pixel at a time, starting from the other side of it
+        let mut front = (0..mask.len())
+            .filter(|&index| match self.placement {
+                OutlinePlacement::Outside => mask[index] > 0,
+                OutlinePlacement::Inside => mask[index] == 0,
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..self.thickness {
+            // Nothing left to grow into
+            if front.is_empty() {
+                break;
+            }
+
+            let mut next = Vec::new();
+            for index in front {
+                let (x, y) = (index % width, index / width);
+                for &(dx, dy) in self.connectivity.offsets() {
+                    let (Some(x), Some(y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy))
+                    else {
+                        continue;
+                    };
+                    if x >= width || y >= height {
+                        continue;
+                    }
+
+                    let neighbour = x + y * width;
+                    if grows_into(mask[neighbour]) {
+                        mask[neighbour] = -1;
+                        next.push(neighbour);
+                    }
+                }
+            }
+
+            front = next;
+        }
+    }
+
+    /// Whether the edges are colored after the symmetry is applied instead of with the body.
+    pub(crate) fn colors_separately(&self, options: &Options) -> bool {
+        self.colors == EdgeColors::Selective || options.light.is_some()
+    }
+
+    /// Color the edges of the full sprite, where every edge still has the color of the body.
+    pub(crate) fn color(
+        &self,
+        colors: &mut [[u8; 4]],
+        cells: &[i8],
+        (width, height): (usize, usize),
+        options: &Options,
+    ) {
+        let size = (width, height);
+        let offsets = Connectivity::Eight.offsets();
+
+        // The color every edge is based on
+        let mut base = colors
+            .iter()
+            .zip(cells)
+            .map(|(&[r, g, b, _], &cell)| {
+                (cell > 0 || self.colors == EdgeColors::Own).then_some([r, g, b])
+            })
+            .collect::<Vec<_>>();
+        if self.colors == EdgeColors::Selective {
+            spread_body_colors(&mut base, cells, size);
+        }
+
+        for index in 0..cells.len() {
+            if cells[index] != -1 {
+                continue;
+            }
+
+            // Edges without any body nearby keep their own color
+            let [r, g, b, a] = colors[index];
+            let base = base[index].unwrap_or([r, g, b]);
+
+            let brightness = match options.light {
+                Some(light) => {
+                    // Points away from the shape
+                    let mut normal = [0.0, 0.0];
+                    for (neighbour, &(dx, dy)) in neighbours(index, size).zip(offsets) {
+                        if neighbour.is_none_or(|neighbour| cells[neighbour] == 0) {
+                            normal[0] += dx as f32;
+                            normal[1] += dy as f32;
+                        }
+                    }
+                    let length = (normal[0] * normal[0] + normal[1] * normal[1]).sqrt();
+                    if length > 0.0 {
+                        normal = normal.map(|axis| axis / length);
+                    }
+
+                    light.brightness(options.edge_brightness, normal)
+                }
+                None => options.edge_brightness,
+            };
+            let [r, g, b] = darken(base, brightness, options);
+            colors[index] = [r, g, b, a];
+        }
+    }
+
+    /// Make the edges colored in the mask match the outline generated on the full sprite.
+    ///
+    /// The symmetry can move cells from the border of the mask next to each other or next to
+    /// empty pixels, the edges missing there get the colors of the adjacent body.
+    pub(crate) fn repair_seams(
+        &self,
+        colors: &mut [[u8; 4]],
+        cells: &mut [i8],
+        mask_cells: &[i8],
+        empty: [u8; 4],
+        size: (usize, usize),
+        options: &Options,
+    ) {
+        for index in 0..cells.len() {
+            if mask_cells[index] != -1 {
+                continue;
+            }
+
+            if cells[index] == 0 {
+                // The radial symmetry can move an edge away from its body
+                colors[index] = empty;
+            } else if cells[index] > 0 {
+                // The color is already darkened, so it has to stay an edge
+                cells[index] = -1;
+            }
+        }
+
+        let missing = (0..cells.len())
+            .filter(|&index| cells[index] == -1 && mask_cells[index] != -1)
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return;
+        }
+
+        if !options.colored {
+            let outline = options.monochrome.unwrap_or_default().outline();
+            for index in missing {
+                colors[index] = outline;
+            }
+
+            return;
+        }
+
+        // Edges placed inside the body are based on their own color
+        let mut base = (0..cells.len())
+            .map(|index| {
+                let [r, g, b, _] = colors[index];
+
+                (cells[index] > 0 || mask_cells[index] > 0).then_some([r, g, b])
+            })
+            .collect::<Vec<_>>();
+        spread_body_colors(&mut base, cells, size);
+
+        let separately = self.colors_separately(options);
+        for index in missing {
+            let Some(base) = base[index] else {
+                continue;
+            };
+
+            // Edges colored later only need a base color
+            let [r, g, b] = if separately {
+                base
+            } else {
+                darken(base, options.edge_brightness, options)
+            };
+            colors[index] = [r, g, b, 0xFF];
+        }
+    }
+}
+
+/// Indices of the 8 neighbours of a pixel, `None` when outside of the sprite.
+fn neighbours(
+    index: usize,
+    (width, height): (usize, usize),
+) -> impl Iterator<Item = Option<usize>> {
+    let (x, y) = (index % width, index / width);
+
+    Connectivity::Eight.offsets().iter().map(move |&(dx, dy)| {
+        let x = x.checked_add_signed(dx).filter(|&x| x < width)?;
+        let y = y.checked_add_signed(dy).filter(|&y| y < height)?;
+
+        Some(x + y * width)
+    })
+}
+
+/// Spread the colors of the body outwards over the edges without a color, so thick outlines get
+/// colors as well.
+fn spread_body_colors(base: &mut [Option<[u8; 3]>], cells: &[i8], size: (usize, usize)) {
+    loop {
+        let resolved = (0..cells.len())
+            .filter(|&index| cells[index] == -1 && base[index].is_none())
+            .filter_map(|index| {
+                let mut sum = [0u32; 3];
+                let mut count = 0;
+                for rgb in neighbours(index, size)
+                    .flatten()
+                    .filter_map(|index| base[index])
+                {
+                    for channel in 0..3 {
+                        sum[channel] += rgb[channel] as u32;
+                    }
+                    count += 1;
+                }
+
+                (count > 0).then(|| (index, sum.map(|channel| (channel / count) as u8)))
+            })
+            .collect::<Vec<_>>();
+        if resolved.is_empty() {
+            break;
+        }
+
+        for (index, rgb) in resolved {
+            base[index] = Some(rgb);
+        }
+    }
+}
+
+/// Darken the color of an edge, with a palette the nearest darker color is picked.
+fn darken(base: [u8; 3], brightness: f32, options: &Options) -> [u8; 3] {
+    let target = base.map(|channel| (channel as f32 * brightness).min(255.0) as u8);
+
+    match &options.color_mode {
+        ColorMode::Free => target,
+        ColorMode::Palette(palette) => {
+            palette.colors()[palette.darker(palette.nearest(base), target)]
+        }
+    }
+}
+
+impl Default for Outline {
+    /// - `thickness`: `1`
+    /// - `connectivity`: `Connectivity::Four`
+    /// - `placement`: `OutlinePlacement::Outside`
+    /// - `colors`: `EdgeColors::Own`
+    fn default() -> Self {
+        Self {
+            thickness: 1,
+            connectivity: Connectivity::Four,
+            placement: OutlinePlacement::Outside,
+            colors: EdgeColors::Own,
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use crate::Error;
+
+/// A fixed list of colors the output of a sprite is constrained to.
+///
+/// ```
+/// use sprite_gen::Palette;
+///
+/// let palette = Palette::new(vec![[0x00, 0x00, 0x00], [0xFF, 0x00, 0x00], [0xFF, 0xFF, 0xFF]]);
+/// assert_eq!(palette.quantize([0xE0, 0x10, 0x20]), [0xFF, 0x00, 0x00]);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Palette {
+    /// All colors as `[r, g, b]`.
+    colors: Vec<[u8; 3]>,
+}
+
+/// File formats a [`Palette`] can be parsed from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PaletteFormat {
+    /// GIMP palette, `.gpl`.
+    Gimp,
+    /// One `RRGGBB` color per line, `.hex`.
+    Hex,
+    /// Paint.NET palette with one `AARRGGBB` color per line, `.txt`.
+    PaintNet,
+    /// JASC palette, `.pal`.
+    Jasc,
+}
+
+impl PaletteFormat {
+    /// Guess the format from a file extension, without the leading dot.
+    ///
+    /// ```
+    /// use sprite_gen::PaletteFormat;
+    ///
+    /// assert_eq!(PaletteFormat::from_extension("GPL"), Some(PaletteFormat::Gimp));
+    /// assert_eq!(PaletteFormat::from_extension("png"), None);
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "gpl" => Some(PaletteFormat::Gimp),
+            "hex" => Some(PaletteFormat::Hex),
+            "txt" => Some(PaletteFormat::PaintNet),
+            "pal" => Some(PaletteFormat::Jasc),
+            _ => None,
+        }
+    }
+}
+
+impl Palette {
+    /// Construct a palette from a list of `[r, g, b]` colors.
+    pub fn new(colors: Vec<[u8; 3]>) -> Self {
+        Self { colors }
+    }
+
+    /// Construct a palette from a list of colors packed as `0xRRGGBB`.
+    ///
+    /// ```
+    /// use sprite_gen::Palette;
+    ///
+    /// let palette = Palette::from_hex(&[0x1A1C2C, 0x5D275D]);
+    /// assert_eq!(palette.colors(), &[[0x1A, 0x1C, 0x2C], [0x5D, 0x27, 0x5D]]);
+    /// ```
+    pub fn from_hex(colors: &[u32]) -> Self {
+        colors
+            .iter()
+            .map(|color| {
+                let [_, r, g, b] = color.to_be_bytes();
+                [r, g, b]
+            })
+            .collect()
+    }
+
+    /// Parse the contents of a palette file.
+    ///
+    /// ```
+    /// use sprite_gen::{Palette, PaletteFormat};
+    ///
+    /// let gimp = "GIMP Palette
+    /// Name: Example
+    /// Columns: 2
+    /// #
+    ///  26  28  44\tDark
+    /// 255 205 117\tLight
+    /// ";
+    /// let palette = Palette::parse(gimp, PaletteFormat::Gimp)?;
+    /// assert_eq!(palette.colors(), &[[26, 28, 44], [255, 205, 117]]);
+    ///
+    /// let hex = "1a1c2c\nffcd75\n";
+    /// assert_eq!(Palette::parse(hex, PaletteFormat::Hex)?, palette);
+    ///
+    /// let paint_net = "; paint.net Palette File\nFF1A1C2C\nFFFFCD75\n";
+    /// assert_eq!(Palette::parse(paint_net, PaletteFormat::PaintNet)?, palette);
+    ///
+    /// let jasc = "JASC-PAL\n0100\n2\n26 28 44\n255 205 117\n";
+    /// assert_eq!(Palette::parse(jasc, PaletteFormat::Jasc)?, palette);
+    /// # Ok::<(), sprite_gen::Error>(())
+    /// ```
+    pub fn parse(contents: &str, format: PaletteFormat) -> Result<Self, Error> {
+        let palette = match format {
+            PaletteFormat::Gimp => Self::parse_gimp(contents)?,
+            PaletteFormat::Hex => Self::parse_hex_lines(contents, 6, ";")?,
+            PaletteFormat::PaintNet => Self::parse_hex_lines(contents, 8, ";")?,
+            PaletteFormat::Jasc => Self::parse_jasc(contents)?,
+        };
+
+        if palette.is_empty() {
+            return Err(Error::EmptyPalette);
+        }
+
+        Ok(palette)
+    }
+
+    /// Parse a GIMP palette, the color name after the channels is ignored.
+    fn parse_gimp(contents: &str) -> Result<Self, Error> {
+        let mut lines = numbered_lines(contents);
+        match lines.next() {
+            Some((_, "GIMP Palette")) => (),
+            _ => return Err(invalid_palette(1, "missing 'GIMP Palette' header")),
+        }
+
+        lines
+            .filter(|(_, line)| {
+                !line.starts_with('#')
+                    && !line.starts_with("Name:")
+                    && !line.starts_with("Columns:")
+            })
+            .map(|(line_number, line)| parse_decimal_color(line_number, line))
+            .collect()
+    }
+
+    /// Parse a JASC palette, the amount of colors in the header must match.
+    fn parse_jasc(contents: &str) -> Result<Self, Error> {
+        let mut lines = numbered_lines(contents);
+        if !matches!(lines.next(), Some((_, "JASC-PAL"))) {
+            return Err(invalid_palette(1, "missing 'JASC-PAL' header"));
+        }
+        if !matches!(lines.next(), Some((_, "0100"))) {
+            return Err(invalid_palette(2, "unsupported version, expected '0100'"));
+        }
+        let amount = match lines.next() {
+            Some((_, line)) => line
+                .parse::<usize>()
+                .map_err(|_| invalid_palette(3, "invalid amount of colors"))?,
+            None => return Err(invalid_palette(3, "missing amount of colors")),
+        };
+
+        let palette = lines
+            .map(|(line_number, line)| parse_decimal_color(line_number, line))
+            .collect::<Result<Self, _>>()?;
+        if palette.len() != amount {
+            return Err(invalid_palette(
+                3,
+                "amount of colors doesn't match the header",
+            ));
+        }
+
+        Ok(palette)
+    }
+
+    /// Parse a palette where every color is a hexadecimal number of `digits` long.
+    ///
+    /// When the number has 8 digits the first two are the alpha channel, which is ignored.
+    fn parse_hex_lines(contents: &str, digits: usize, comments: &str) -> Result<Self, Error> {
+        numbered_lines(contents)
+            .filter(|(_, line)| !line.starts_with(|c| comments.contains(c)))
+            .map(|(line_number, line)| {
+                let hex = line.trim_start_matches('#');
+                if hex.len() != digits {
+                    return Err(invalid_palette(line_number, "invalid hexadecimal color"));
+                }
+
+                let [_, r, g, b] = u32::from_str_radix(hex, 16)
+                    .map_err(|_| invalid_palette(line_number, "invalid hexadecimal color"))?
+                    .to_be_bytes();
+
+                Ok([r, g, b])
+            })
+            .collect()
+    }
+
+    /// All colors as `[r, g, b]`.
+    pub fn colors(&self) -> &[[u8; 3]] {
+        &self.colors
+    }
+
+    /// Amount of colors.
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Whether the palette doesn't have any colors.
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Index of the color closest to the passed color.
+    ///
+    /// # Panics
+    ///
+    /// When the palette is empty.
+    pub fn nearest(&self, rgb: [u8; 3]) -> usize {
+        self.closest_in(rgb, |_| true)
+            .expect("Palette doesn't contain any colors")
+    }
+
+    /// The color closest to the passed color.
+    ///
+    /// # Panics
+    ///
+    /// When the palette is empty.
+    pub fn quantize(&self, rgb: [u8; 3]) -> [u8; 3] {
+        self.colors[self.nearest(rgb)]
+    }
+
+    /// Index of the color closest to the target that's darker than the color at the index.
+    ///
+    /// Falls back to the darkest color when nothing is darker.
+    pub(crate) fn darker(&self, index: usize, target: [u8; 3]) -> usize {
+        let max = luminance(self.colors[index]);
+
+        self.closest_in(target, |color| luminance(color) < max)
+            .unwrap_or_else(|| {
+                (0..self.colors.len())
+                    .min_by_key(|&index| luminance(self.colors[index]))
+                    .unwrap_or(index)
+            })
+    }
+
+    /// Index of the closest color that passes the filter.
+    fn closest_in<F>(&self, rgb: [u8; 3], filter: F) -> Option<usize>
+    where
+        F: Fn([u8; 3]) -> bool,
+    {
+        self.colors
+            .iter()
+            .enumerate()
+            .filter(|(_, &color)| filter(color))
+            .min_by_key(|(_, &color)| distance(rgb, color))
+            .map(|(index, _)| index)
+    }
+}
+
+impl FromIterator<[u8; 3]> for Palette {
+    fn from_iter<T: IntoIterator<Item = [u8; 3]>>(iter: T) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+/// Trimmed lines that aren't empty, with their line number starting at `1`.
+fn numbered_lines(contents: &str) -> impl Iterator<Item = (usize, &str)> {
+    contents
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.is_empty())
+}
+
+/// Parse a color of three whitespace separated decimal channels, anything after that is ignored.
+fn parse_decimal_color(line_number: usize, line: &str) -> Result<[u8; 3], Error> {
+    let mut channels = line.split_whitespace().map(str::parse::<u8>);
+    let mut channel = || {
+        channels
+            .next()
+            .and_then(Result::ok)
+            .ok_or_else(|| invalid_palette(line_number, "expected three channels from 0 to 255"))
+    };
+
+    Ok([channel()?, channel()?, channel()?])
+}
+
+/// Construct the error for an invalid line in a palette file.
+fn invalid_palette(line: usize, reason: &'static str) -> Error {
+    Error::InvalidPalette { line, reason }
+}
+
+/// Weighted squared distance between two colors, green differences are the most noticeable.
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    let channel = |index: usize| (a[index] as i32 - b[index] as i32).unsigned_abs().pow(2);
+
+    channel(0) * 3 + channel(1) * 4 + channel(2) * 2
+}
+
+/// Relative luminance, scaled to integers.
+pub(crate) fn luminance([r, g, b]: [u8; 3]) -> u32 {
+    r as u32 * 2126 + g as u32 * 7152 + b as u32 * 722
+}
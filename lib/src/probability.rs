@@ -0,0 +1,219 @@
+use crate::{Error, Mask, MaskValue, Material};
+
+/// Chances of a single cell of a [`ProbabilityMask`] to turn into a body or a border.
+///
+/// First the cell has a chance of `fill` to become a body, if it doesn't it has a chance of
+/// `border` to become a border, otherwise it's empty.
+///
+/// ```
+/// use sprite_gen::{CellProbability, MaskValue};
+///
+/// assert_eq!(
+///     MaskValue::Body2.probability(),
+///     CellProbability {
+///         fill: 0.5,
+///         border: 1.0,
+///         ..Default::default()
+///     }
+/// );
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct CellProbability {
+    /// Chance from `0.0` - `1.0` of the cell becoming a body.
+    pub fill: f32,
+    /// Chance from `0.0` - `1.0` of the cell becoming a border when it's not a body.
+    pub border: f32,
+    /// What the cell is made of when it becomes a body.
+    pub material: Material,
+}
+
+impl CellProbability {
+    /// Whether both chances are between `0.0` and `1.0`.
+    pub(crate) fn is_valid(&self) -> bool {
+        // Also catches NaN
+        (0.0..=1.0).contains(&self.fill) && (0.0..=1.0).contains(&self.border)
+    }
+}
+
+impl From<MaskValue> for CellProbability {
+    fn from(from: MaskValue) -> Self {
+        from.probability()
+    }
+}
+
+/// A grid of [`CellProbability`] cells describing the shape of a sprite.
+///
+/// This is a more fine-grained version of [`Mask`], where every cell can have its own chance of
+/// being filled instead of the fixed 50% of [`MaskValue::Body1`] and [`MaskValue::Body2`].
+///
+/// ```
+/// use sprite_gen::{generate_probabilities, Mask, Options, ProbabilityMask};
+///
+/// // A core that's usually filled with a fringe that's rarely filled
+/// let fill = [
+///     20, 20, 20, 20,
+///     20, 240, 240, 20,
+///     20, 240, 240, 20,
+///     20, 20, 20, 20,
+/// ];
+/// let mask = ProbabilityMask::from_grayscale(&fill, &[0; 16], 4)?;
+/// let sprite = generate_probabilities(&mask, Options::default());
+/// assert_eq!(sprite.size(), (4, 4));
+///
+/// // Regular masks behave exactly the same when converted
+/// let mask: Mask = "..#\n.12\n".parse()?;
+/// assert_eq!(
+///     generate_probabilities(&ProbabilityMask::from(&mask), Options::default()),
+///     sprite_gen::generate(mask.cells(), mask.width(), Options::default())
+/// );
+/// # Ok::<(), sprite_gen::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbabilityMask {
+    /// Amount of cells in a row.
+    width: usize,
+    /// Amount of rows.
+    height: usize,
+    /// All cells, row by row.
+    cells: Vec<CellProbability>,
+}
+
+impl ProbabilityMask {
+    /// Construct a mask where every cell is always empty.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![CellProbability::default(); width * height],
+        }
+    }
+
+    /// Construct a mask from a buffer of cells, row by row.
+    pub fn from_cells(cells: Vec<CellProbability>, width: usize) -> Result<Self, Error> {
+        if width == 0 {
+            return Err(Error::ZeroWidth);
+        }
+        if !cells.len().is_multiple_of(width) {
+            return Err(Error::RaggedMask {
+                len: cells.len(),
+                width,
+            });
+        }
+        if let Some(index) = cells.iter().position(|cell| !cell.is_valid()) {
+            return Err(Error::InvalidProbability { index });
+        }
+
+        Ok(Self {
+            width,
+            height: cells.len() / width,
+            cells,
+        })
+    }
+
+    /// Construct a mask from two grayscale images, for example loaded from PNG files.
+    ///
+    /// Black is a chance of `0.0` and white a chance of `1.0`.
+    /// Both images must be of the same size.
+    ///
+    /// ```
+    /// use sprite_gen::{Error, ProbabilityMask};
+    ///
+    /// let mask = ProbabilityMask::from_grayscale(&[0x00, 0xFF], &[0xFF, 0x00], 2)?;
+    /// assert_eq!(mask.get(1, 0).unwrap().fill, 1.0);
+    ///
+    /// assert_eq!(
+    ///     ProbabilityMask::from_grayscale(&[0x00, 0xFF], &[0xFF], 2),
+    ///     Err(Error::ImageSizeMismatch { fill: 2, border: 1 })
+    /// );
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn from_grayscale(fill: &[u8], border: &[u8], width: usize) -> Result<Self, Error> {
+        if fill.len() != border.len() {
+            return Err(Error::ImageSizeMismatch {
+                fill: fill.len(),
+                border: border.len(),
+            });
+        }
+
+        Self::from_cells(
+            fill.iter()
+                .zip(border)
+                .map(|(&fill, &border)| CellProbability {
+                    fill: fill as f32 / u8::MAX as f32,
+                    border: border as f32 / u8::MAX as f32,
+                    ..Default::default()
+                })
+                .collect(),
+            width,
+        )
+    }
+
+    /// Amount of cells in a row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Amount of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Width and height in cells.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// All cells, row by row.
+    pub fn cells(&self) -> &[CellProbability] {
+        &self.cells
+    }
+
+    /// Get a single cell, `None` when the coordinates are outside of the mask.
+    pub fn get(&self, x: usize, y: usize) -> Option<CellProbability> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.cells.get(x + y * self.width).copied()
+    }
+
+    /// Set a single cell, does nothing when the coordinates are outside of the mask.
+    ///
+    /// The chances are clamped between `0.0` and `1.0`, `NaN` becomes `0.0`.
+    ///
+    /// ```
+    /// use sprite_gen::{CellProbability, ProbabilityMask};
+    ///
+    /// let mut mask = ProbabilityMask::new(1, 1);
+    /// mask.set(0, 0, CellProbability { fill: f32::NAN, border: 2.0, ..Default::default() });
+    /// assert_eq!(mask.get(0, 0).unwrap().fill, 0.0);
+    /// assert_eq!(mask.get(0, 0).unwrap().border, 1.0);
+    /// ```
+    pub fn set(&mut self, x: usize, y: usize, value: CellProbability) {
+        let chance = |chance: f32| {
+            if chance.is_nan() {
+                0.0
+            } else {
+                chance.clamp(0.0, 1.0)
+            }
+        };
+
+        if x < self.width && y < self.height {
+            self.cells[x + y * self.width] = CellProbability {
+                fill: chance(value.fill),
+                border: chance(value.border),
+                ..value
+            };
+        }
+    }
+}
+
+impl From<&Mask> for ProbabilityMask {
+    fn from(from: &Mask) -> Self {
+        Self {
+            width: from.width(),
+            height: from.height(),
+            cells: from.cells().iter().map(MaskValue::probability).collect(),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::Error;
+
+/// How the random numbers deciding whether a cell is filled are picked.
+///
+/// ```
+/// use sprite_gen::{generate, MaskValue, Options, PixelFormat, Sampling};
+///
+/// let mask = vec![MaskValue::Body1; 32 * 32];
+/// let sprite = generate(
+///     &mask,
+///     32,
+///     Options {
+///         sampling: Sampling::Noise {
+///             frequency: 0.15,
+///             threshold: 0.5,
+///         },
+///         pixel_format: PixelFormat::Rgba,
+///         ..Default::default()
+///     },
+/// );
+///
+/// // Neighbouring cells mostly end up the same
+/// let filled = |x, y| sprite.get(x, y).unwrap() & 0xFF != 0;
+/// let same = (0..31)
+///     .flat_map(|y| (0..31).map(move |x| (x, y)))
+///     .filter(|&(x, y)| filled(x, y) == filled(x + 1, y))
+///     .count();
+/// assert!(same > 31 * 31 * 3 / 4);
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Sampling {
+    /// Every cell is decided separately.
+    #[default]
+    Independent,
+    /// Cells are decided by a smooth noise field, so neighbouring cells are filled together.
+    ///
+    /// [`crate::MaskValue::Solid`] and [`crate::MaskValue::Empty`] cells are never changed.
+    Noise {
+        /// Amount of noise features per cell, lower values give larger blobs.
+        frequency: f32,
+        /// A value from `0.0` - `1.0`, higher values give fewer filled cells.
+        ///
+        /// At `0.5` cells with a chance of 50% are filled about half of the time.
+        threshold: f32,
+    },
+}
+
+impl Sampling {
+    /// Check whether the settings can be used.
+    pub(crate) fn validate(self) -> Result<(), Error> {
+        match self {
+            Sampling::Independent => Ok(()),
+            Sampling::Noise { frequency, .. } if !(frequency.is_finite() && frequency > 0.0) => {
+                Err(Error::InvalidSampling {
+                    reason: "noise frequency must be larger than 0.0",
+                })
+            }
+            // Also catches NaN
+            Sampling::Noise { threshold, .. } if !(0.0..=1.0).contains(&threshold) => {
+                Err(Error::InvalidSampling {
+                    reason: "noise threshold must be between 0.0 and 1.0",
+                })
+            }
+            Sampling::Noise { .. } => Ok(()),
+        }
+    }
+}
+
+/// Smooth value noise from `0.0` - `1.0` at a cell of the mask.
+pub(crate) fn value_noise(seed: u64, x: usize, y: usize, frequency: f32) -> f32 {
+    // Sample in the middle of the cell
+    let x = (x as f32 + 0.5) * frequency;
+    let y = (y as f32 + 0.5) * frequency;
+
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (smoothstep(x - x0), smoothstep(y - y0));
+    let (x0, y0) = (x0 as i64, y0 as i64);
+
+    let lattice = |x: i64, y: i64| {
+        let hash = mix(mix(seed ^ x as u64) ^ y as u64);
+        // Use the upper 24 bits so the value fits exactly in a float
+        (hash >> 40) as f32 / ((1 << 24) - 1) as f32
+    };
+
+    let top = lerp(lattice(x0, y0), lattice(x0 + 1, y0), tx);
+    let bottom = lerp(lattice(x0, y0 + 1), lattice(x0 + 1, y0 + 1), tx);
+
+    lerp(top, bottom, ty)
+}
+
+/// Smooth the transition between lattice points.
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Linear interpolation between two values.
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Scramble the bits, the finalizer of SplitMix64.
+fn mix(mut value: u64) -> u64 {
+    value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+    value ^ (value >> 31)
+}
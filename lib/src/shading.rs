@@ -0,0 +1,289 @@
+use crate::{ColorMode, Light, Material, Options};
+
+/// Light used for the highlights when [`Options::light`] is not set, from the top-left.
+const DEFAULT_LIGHT: Light = Light {
+    angle: 315.0,
+    strength: 1.0,
+};
+
+/// Shading of the body based on the generated shape.
+///
+/// The body is treated as a rounded surface that's highest at the pixels furthest away from the
+/// edges. All strengths are values from `0.0` - `1.0`, `0.0` disables that part.
+///
+/// ```
+/// use sprite_gen::{generate, MaskValue, Options, PixelFormat, Shading};
+///
+/// let mask = vec![MaskValue::Body1; 12 * 12];
+/// let options = Options {
+///     mirror_x: true,
+///     pixel_format: PixelFormat::Rgba,
+///     ..Default::default()
+/// };
+/// let flat = generate(&mask, 12, options.clone());
+/// let shaded = generate(
+///     &mask,
+///     12,
+///     Options {
+///         shading: Shading {
+///             rounding: 1.0,
+///             highlights: 1.0,
+///             occlusion: 1.0,
+///         },
+///         ..options
+///     },
+/// );
+///
+/// // Only the colors change, not the shape
+/// let alpha = |pixels: &[u32]| pixels.iter().map(|pixel| pixel & 0xFF).collect::<Vec<_>>();
+/// assert_eq!(alpha(flat.pixels()), alpha(shaded.pixels()));
+/// assert_ne!(flat, shaded);
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Shading {
+    /// How much darker the body gets towards the edges, making it look rounded.
+    ///
+    /// When [`Options::light`] is set the side facing away from the light also gets darker.
+    pub rounding: f32,
+    /// How bright the pixels reflecting the light are.
+    ///
+    /// Uses the direction of [`Options::light`], or light from the top-left when it's not set.
+    pub highlights: f32,
+    /// How much darker the body gets near concave areas of the shape.
+    pub occlusion: f32,
+}
+
+impl Shading {
+    /// Whether any of the parts changes the colors.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.rounding > 0.0 || self.highlights > 0.0 || self.occlusion > 0.0
+    }
+
+    /// All strengths with their names, for validating them.
+    pub(crate) fn strengths(&self) -> [(&'static str, f32); 3] {
+        [
+            ("shading.rounding", self.rounding),
+            ("shading.highlights", self.highlights),
+            ("shading.occlusion", self.occlusion),
+        ]
+    }
+
+    /// Shade the body pixels of the full sprite, returns which pixels got a highlight.
+    pub(crate) fn apply(
+        &self,
+        colors: &mut [[u8; 4]],
+        cells: &[i8],
+        materials: &[Material],
+        size: (usize, usize),
+        options: &Options,
+    ) -> Vec<bool> {
+        let (width, height) = size;
+        let mut highlights = vec![false; cells.len()];
+        let body = cells.iter().map(|&cell| cell > 0).collect::<Vec<_>>();
+        let heights = height_map(&body, size);
+        let occlusion = occlusion_map(cells, size);
+
+        let light = options.light.unwrap_or(DEFAULT_LIGHT);
+        let [light_x, light_y] = light.direction();
+        // The light comes from the front at an angle of 45°
+        let halfway = normalize([light_x, light_y, 1.0 + std::f32::consts::SQRT_2]);
+
+        for y in 0..height {
+            for x in 0..width {
+                let index = x + y * width;
+                // Glowing pixels are not affected by the light
+                if cells[index] <= 0 || materials[index] == Material::Emissive {
+                    continue;
+                }
+
+                let normal = surface_normal(&heights, size, x, y);
+
+                // Darker towards the edges and away from the light
+                let mut brightness = 1.0 - self.rounding * (1.0 - heights[index]) * 0.6;
+                if let Some(light) = options.light {
+                    brightness = light.brightness(
+                        brightness,
+                        [normal[0] * self.rounding, normal[1] * self.rounding],
+                    );
+                }
+                brightness *= 1.0 - self.occlusion * occlusion[index] * 0.7;
+
+                let [r, g, b, a] = colors[index];
+                let mut rgb = [r, g, b].map(|channel| channel as f32 * brightness);
+
+                // Only the pixels that almost directly reflect the light get a highlight
+                let specular = dot(normal, halfway).max(0.0).powi(32);
+                if specular > 0.5 && self.highlights > 0.0 {
+                    rgb = rgb.map(|channel| channel + (255.0 - channel) * self.highlights * 0.8);
+                    highlights[index] = true;
+                }
+
+                let mut rgb = rgb.map(|channel| channel.round().clamp(0.0, 255.0) as u8);
+                // Otherwise the colors are matched to the palette after the dithering
+                if let ColorMode::Palette(palette) = &options.color_mode {
+                    if !options.dithering.is_enabled() {
+                        rgb = palette.quantize(rgb);
+                    }
+                }
+
+                let [r, g, b] = rgb;
+                colors[index] = [r, g, b, a];
+            }
+        }
+
+        highlights
+    }
+}
+
+/// Normals of the full sprite encoded as colors, derived from the height of the shape.
+///
+/// The channels are `[x, y, z, alpha]` mapped from `-1.0` - `1.0` to `0` - `255`, with `y`
+/// pointing up. Empty pixels are flat and transparent.
+pub(crate) fn normal_map(cells: &[i8], size: (usize, usize)) -> Vec<[u8; 4]> {
+    let (width, height) = size;
+    let filled = cells.iter().map(|&cell| cell != 0).collect::<Vec<_>>();
+    let heights = height_map(&filled, size);
+
+    (0..width * height)
+        .map(|index| {
+            if !filled[index] {
+                return [0x80, 0x80, 0xFF, 0x00];
+            }
+
+            let [x, y, z] = surface_normal(&heights, size, index % width, index / width);
+            let channel = |axis: f32| ((axis * 0.5 + 0.5) * 255.0).round() as u8;
+
+            [channel(x), channel(-y), channel(z), 0xFF]
+        })
+        .collect()
+}
+
+/// Height of every pixel of the full sprite from `0.0` - `1.0`, as a rounded surface.
+///
+/// Everything that's not filled has a height of `0.0`, outside of the sprite is also `0.0`.
+pub(crate) fn height_map(filled: &[bool], (width, height): (usize, usize)) -> Vec<f32> {
+    // Chamfer distance to the closest pixel that's not filled, `3` per straight step
+    let mut distances = filled
+        .iter()
+        .map(|&filled| if filled { u32::MAX } else { 0 })
+        .collect::<Vec<_>>();
+    let distance = |distances: &[u32], x: usize, y: usize, dx: isize, dy: isize| {
+        let (Some(x), Some(y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
+            return 0;
+        };
+        if x >= width || y >= height {
+            return 0;
+        }
+
+        distances[x + y * width]
+    };
+
+    // Forward pass from the top-left and backward pass from the bottom-right
+    for (backward, offsets) in [
+        (false, [(-1, -1, 4), (0, -1, 3), (1, -1, 4), (-1, 0, 3)]),
+        (true, [(1, 1, 4), (0, 1, 3), (-1, 1, 4), (1, 0, 3)]),
+    ] {
+        for i in 0..width * height {
+            let index = if backward { width * height - i - 1 } else { i };
+            let (x, y) = (index % width, index / width);
+            if distances[index] == 0 {
+                continue;
+            }
+
+            for (dx, dy, cost) in offsets {
+                let through = distance(&distances, x, y, dx, dy).saturating_add(cost);
+                distances[index] = distances[index].min(through);
+            }
+        }
+    }
+
+    let max = distances.iter().copied().max().unwrap_or(0).max(1) as f32;
+
+    distances
+        .into_iter()
+        .map(|distance| {
+            // A circular profile, steep at the edges and flat in the middle
+            let t = distance as f32 / max;
+            (1.0 - (1.0 - t) * (1.0 - t)).sqrt()
+        })
+        .collect()
+}
+
+/// Unit normal of the rounded surface at a pixel, `x` points right, `y` down and `z` towards the
+/// viewer.
+pub(crate) fn surface_normal(
+    heights: &[f32],
+    (width, height): (usize, usize),
+    x: usize,
+    y: usize,
+) -> [f32; 3] {
+    let at = |x: Option<usize>, y: Option<usize>| match (x, y) {
+        (Some(x), Some(y)) if x < width && y < height => heights[x + y * width],
+        _ => 0.0,
+    };
+
+    let slope_x = at(x.checked_sub(1), Some(y)) - at(x.checked_add(1), Some(y));
+    let slope_y = at(Some(x), y.checked_sub(1)) - at(Some(x), y.checked_add(1));
+
+    // Exaggerate the slopes so the small sprites still have clear sides
+    normalize([slope_x * 2.0, slope_y * 2.0, 1.0])
+}
+
+/// How much every body pixel is occluded by the shape around it, from `0.0` - `1.0`.
+fn occlusion_map(cells: &[i8], (width, height): (usize, usize)) -> Vec<f32> {
+    const RADIUS: usize = 2;
+
+    let window = |x: usize, y: usize| {
+        let xs = x.saturating_sub(RADIUS)..(x + RADIUS + 1).min(width);
+        let ys = y.saturating_sub(RADIUS)..(y + RADIUS + 1).min(height);
+
+        ys.flat_map(move |y| xs.clone().map(move |x| (x, y)))
+    };
+
+    // How enclosed every empty pixel is, along a straight edge about half of the window is filled
+    let enclosed = (0..width * height)
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            if cells[index] != 0 {
+                return 0.0;
+            }
+
+            let filled = window(x, y)
+                .filter(|&(x, y)| cells[x + y * width] != 0)
+                .count();
+            let total = (RADIUS * 2 + 1).pow(2);
+
+            ((filled as f32 / total as f32 - 0.5) * 2.0).clamp(0.0, 1.0)
+        })
+        .collect::<Vec<_>>();
+
+    (0..width * height)
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            if cells[index] <= 0 {
+                return 0.0;
+            }
+
+            // Fade out with the distance to the enclosed pixel
+            window(x, y)
+                .map(|(other_x, other_y)| {
+                    let distance = x.abs_diff(other_x).max(y.abs_diff(other_y));
+
+                    enclosed[other_x + other_y * width] * (1.0 - distance as f32 / 3.0)
+                })
+                .fold(0.0, f32::max)
+        })
+        .collect()
+}
+
+/// Scale a vector to a length of `1.0`.
+fn normalize([x, y, z]: [f32; 3]) -> [f32; 3] {
+    let length = (x * x + y * y + z * z).sqrt();
+
+    [x / length, y / length, z / length]
+}
+
+/// Dot product of two vectors.
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
@@ -0,0 +1,293 @@
+/// How the pixels of a generated [`Sprite`] are packed into `u32` values.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum PixelFormat {
+    /// Every pixel is `0x00RRGGBB`, empty pixels are `0xFFFFFFFF`.
+    ///
+    /// When [`crate::Options::colored`] is `false` and [`crate::Options::monochrome`] is `None`
+    /// every pixel is `0x00000000` for edges and `0xFFFFFFFF` for everything else.
+    #[default]
+    Rgb,
+    /// Every pixel is `0xRRGGBBAA`, empty pixels are fully transparent and filled pixels opaque.
+    Rgba,
+    /// Every pixel is `0xAARRGGBB`, empty pixels are fully transparent and filled pixels opaque.
+    Argb,
+    /// Every pixel is `0xBBGGRRAA`, empty pixels are fully transparent and filled pixels opaque.
+    Bgra,
+}
+
+impl PixelFormat {
+    /// Pack a color into a single pixel.
+    pub(crate) fn pack(self, [r, g, b, a]: [u8; 4]) -> u32 {
+        match self {
+            PixelFormat::Rgb if a == 0 => 0xFF_FF_FF_FF,
+            PixelFormat::Rgb => u32::from_be_bytes([0, r, g, b]),
+            PixelFormat::Rgba => u32::from_be_bytes([r, g, b, a]),
+            PixelFormat::Argb => u32::from_be_bytes([a, r, g, b]),
+            PixelFormat::Bgra => u32::from_be_bytes([b, g, r, a]),
+        }
+    }
+}
+
+/// A generated sprite.
+///
+/// Holds the final dimensions after mirroring so they don't have to be calculated again from
+/// the mask.
+///
+/// ```
+/// use sprite_gen::{generate, MaskValue, Options};
+///
+/// let mask = vec![MaskValue::Body1; 12 * 8];
+/// let sprite = generate(
+///     &mask,
+///     12,
+///     Options {
+///         mirror_x: true,
+///         ..Default::default()
+///     },
+/// );
+/// assert_eq!(sprite.width(), 24);
+/// assert_eq!(sprite.height(), 8);
+/// assert_eq!(sprite.rows().count(), 8);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Sprite {
+    /// Width in pixels.
+    width: usize,
+    /// Height in pixels.
+    height: usize,
+    /// How the pixels are packed.
+    format: PixelFormat,
+    /// All pixels, row by row.
+    pixels: Vec<u32>,
+    /// Normals of all pixels packed in the same format, row by row.
+    normals: Option<Vec<u32>>,
+    /// Only the glowing pixels packed in the same format, row by row.
+    emissive: Option<Vec<u32>>,
+    /// A bit for every body pixel that's turned on, row by row.
+    bits: Option<Vec<u8>>,
+}
+
+impl Sprite {
+    /// Construct a sprite from a buffer, the buffer must be `width * height` long.
+    pub(crate) fn new(width: usize, height: usize, format: PixelFormat, pixels: Vec<u32>) -> Self {
+        debug_assert_eq!(pixels.len(), width * height);
+
+        Self {
+            width,
+            height,
+            format,
+            pixels,
+            normals: None,
+            emissive: None,
+            bits: None,
+        }
+    }
+
+    /// Add a normal map, the buffer must be `width * height` long.
+    pub(crate) fn with_normal_map(mut self, normals: Vec<u32>) -> Self {
+        debug_assert_eq!(normals.len(), self.pixels.len());
+
+        self.normals = Some(normals);
+        self
+    }
+
+    /// Add an emissive map, the buffer must be `width * height` long.
+    pub(crate) fn with_emissive_map(mut self, emissive: Vec<u32>) -> Self {
+        debug_assert_eq!(emissive.len(), self.pixels.len());
+
+        self.emissive = Some(emissive);
+        self
+    }
+
+    /// Add the packed bits of a monochrome sprite.
+    pub(crate) fn with_bits(mut self, bits: Vec<u8>) -> Self {
+        debug_assert_eq!(bits.len(), self.width.div_ceil(8) * self.height);
+
+        self.bits = Some(bits);
+        self
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Width and height in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// How the pixels are packed.
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// All pixels, row by row.
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    /// Take the pixels, row by row.
+    pub fn into_pixels(self) -> Vec<u32> {
+        self.pixels
+    }
+
+    /// Get a single pixel, `None` when the coordinates are outside of the sprite.
+    pub fn get(&self, x: usize, y: usize) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(self.pixels[x + y * self.width])
+    }
+
+    /// The normal map of the sprite, only generated when [`crate::Options::normal_map`] is set.
+    ///
+    /// Every pixel is a normal with the `x`, `y` and `z` axes in the red, green and blue
+    /// channels, mapped from `-1.0` - `1.0` to `0` - `255`. The `y` axis points up.
+    ///
+    /// ```
+    /// use sprite_gen::{generate, MaskValue, Options, PixelFormat};
+    ///
+    /// let mask = vec![MaskValue::Body1; 8 * 8];
+    /// let sprite = generate(
+    ///     &mask,
+    ///     8,
+    ///     Options {
+    ///         mirror_x: true,
+    ///         normal_map: true,
+    ///         pixel_format: PixelFormat::Rgba,
+    ///         ..Default::default()
+    ///     },
+    /// );
+    /// let normals = sprite.normal_map().unwrap();
+    /// assert_eq!(normals.size(), sprite.size());
+    ///
+    /// // The mirrored half points the other way
+    /// for y in 0..8 {
+    ///     for x in 0..8 {
+    ///         let [left, ..] = normals.get(x, y).unwrap().to_be_bytes();
+    ///         let [right, ..] = normals.get(15 - x, y).unwrap().to_be_bytes();
+    ///         assert!((left as i32 + right as i32 - 0xFF).abs() <= 1);
+    ///     }
+    /// }
+    /// ```
+    pub fn normal_map(&self) -> Option<Sprite> {
+        self.normals
+            .as_ref()
+            .map(|normals| Sprite::new(self.width, self.height, self.format, normals.clone()))
+    }
+
+    /// Only the glowing pixels of the sprite, for adding bloom.
+    ///
+    /// Only generated when [`crate::Options::emissive_map`] is set, the pixels that don't glow are
+    /// empty.
+    ///
+    /// ```
+    /// use sprite_gen::{generate, MaskValue, Options, PixelFormat};
+    ///
+    /// let mask = vec![MaskValue::Body1, MaskValue::Emissive, MaskValue::Body1];
+    /// let sprite = generate(
+    ///     &mask,
+    ///     3,
+    ///     Options {
+    ///         emissive_map: true,
+    ///         pixel_format: PixelFormat::Rgba,
+    ///         ..Default::default()
+    ///     },
+    /// );
+    /// let emissive = sprite.emissive_map().unwrap();
+    ///
+    /// assert_eq!(emissive.get(0, 0), Some(0x00000000));
+    /// assert_eq!(emissive.get(1, 0), sprite.get(1, 0));
+    /// ```
+    pub fn emissive_map(&self) -> Option<Sprite> {
+        self.emissive
+            .as_ref()
+            .map(|emissive| Sprite::new(self.width, self.height, self.format, emissive.clone()))
+    }
+
+    /// The pixels packed as a single bit each, for monochrome displays.
+    ///
+    /// Only available when [`crate::Options::colored`] is `false` and
+    /// [`crate::Options::monochrome`] is set. A bit is set for every body pixel that's not turned
+    /// off by [`crate::Options::dithering`], whatever the colors are.
+    /// Every row starts at a new byte with the first pixel in the highest bit, so a row takes
+    /// `(width + 7) / 8` bytes.
+    ///
+    /// ```
+    /// use sprite_gen::{generate, Mask, Monochrome, Options};
+    ///
+    /// let mask: Mask = "dddd.#ddd\n".parse()?;
+    /// let sprite = generate(
+    ///     mask.cells(),
+    ///     mask.width(),
+    ///     Options {
+    ///         colored: false,
+    ///         monochrome: Some(Monochrome::default()),
+    ///         ..Default::default()
+    ///     },
+    /// );
+    /// assert_eq!(sprite.to_1bpp(), Some(&[0b1111_0011, 0b1000_0000][..]));
+    ///
+    /// // Only the shape decides the bits, not the colors
+    /// let sprite = generate(
+    ///     mask.cells(),
+    ///     mask.width(),
+    ///     Options {
+    ///         colored: false,
+    ///         monochrome: Some(Monochrome {
+    ///             outline: [0xFF, 0xFF, 0xFF],
+    ///             background: Some([0xFF, 0xFF, 0xFF]),
+    ///             ..Default::default()
+    ///         }),
+    ///         ..Default::default()
+    ///     },
+    /// );
+    /// assert_eq!(sprite.to_1bpp(), Some(&[0b1111_0011, 0b1000_0000][..]));
+    /// # Ok::<(), sprite_gen::Error>(())
+    /// ```
+    pub fn to_1bpp(&self) -> Option<&[u8]> {
+        self.bits.as_deref()
+    }
+
+    /// The pixels as bytes, in the order of the channels in the name of the format.
+    ///
+    /// This can be passed directly to PNG encoders and engines for [`PixelFormat::Rgba`].
+    ///
+    /// ```
+    /// use sprite_gen::{generate, MaskValue, Options, PixelFormat};
+    ///
+    /// let mask = vec![MaskValue::Empty, MaskValue::Solid];
+    /// let sprite = generate(
+    ///     &mask,
+    ///     2,
+    ///     Options {
+    ///         pixel_format: PixelFormat::Rgba,
+    ///         ..Default::default()
+    ///     },
+    /// );
+    /// let bytes = sprite.to_bytes();
+    /// assert_eq!(bytes.len(), 2 * 4);
+    /// // Empty is transparent and the edge is opaque
+    /// assert_eq!(bytes[3], 0x00);
+    /// assert_eq!(bytes[7], 0xFF);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_be_bytes())
+            .collect()
+    }
+
+    /// Iterate over each row of pixels.
+    pub fn rows(&self) -> std::slice::ChunksExact<'_, u32> {
+        // Chunks of zero are not allowed, an empty sprite has no pixels anyway
+        self.pixels.chunks_exact(self.width.max(1))
+    }
+}
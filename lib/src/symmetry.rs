@@ -0,0 +1,223 @@
+use crate::{Error, Options};
+
+/// How the mask is repeated to build the full sprite.
+///
+/// ```
+/// use sprite_gen::{generate, MaskValue, Options, Symmetry};
+///
+/// let mask = vec![MaskValue::Body1; 8 * 8];
+/// let sprite = |symmetry| {
+///     generate(
+///         &mask,
+///         8,
+///         Options {
+///             symmetry,
+///             ..Default::default()
+///         },
+///     )
+///     .size()
+/// };
+///
+/// assert_eq!(sprite(Symmetry::Rotate2), (16, 8));
+/// assert_eq!(sprite(Symmetry::Rotate4), (16, 16));
+/// assert_eq!(sprite(Symmetry::Diagonal), (8, 8));
+/// assert_eq!(sprite(Symmetry::Radial(6)), (16, 16));
+/// ```
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Symmetry {
+    /// Mirror along the axes set with [`Options::mirror_x`] and [`Options::mirror_y`].
+    #[default]
+    Mirror,
+    /// 2-fold rotational symmetry, the mask is the left half and the right half is the mask
+    /// rotated by 180°.
+    Rotate2,
+    /// 4-fold rotational symmetry, the mask is the top-left quarter and the other quarters are
+    /// the mask rotated by 90°, 180° and 270°.
+    ///
+    /// Requires a square mask.
+    Rotate4,
+    /// Mirror along the diagonal from the top-left to the bottom-right, only the cells of the
+    /// mask on or above that diagonal are used.
+    ///
+    /// Requires a square mask.
+    Diagonal,
+    /// N-fold radial symmetry around the center of the sprite.
+    ///
+    /// The mask is the left half of a single arm pointing up, the top row is the tip of the arm
+    /// and the bottom row the center of the sprite. The arm is mirrored and repeated N times.
+    Radial(u8),
+}
+
+impl Symmetry {
+    /// Check whether the symmetry can be used with the size of the mask.
+    pub(crate) fn validate(self, mask_width: usize, mask_height: usize) -> Result<(), Error> {
+        match self {
+            Symmetry::Rotate4 | Symmetry::Diagonal if mask_width != mask_height => {
+                Err(Error::InvalidSymmetry {
+                    reason: "requires a square mask",
+                })
+            }
+            Symmetry::Radial(0) => Err(Error::InvalidSymmetry {
+                reason: "radial symmetry requires at least one arm",
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// For every pixel of the full sprite which cell of the mask it's copied from.
+pub(crate) struct SymmetryMap {
+    /// Width in pixels.
+    pub(crate) width: usize,
+    /// Height in pixels.
+    pub(crate) height: usize,
+    /// Index into the mask of every pixel, `None` when the pixel is always empty.
+    pub(crate) sources: Vec<Option<usize>>,
+}
+
+impl SymmetryMap {
+    /// Calculate where every pixel is copied from.
+    pub(crate) fn new(mask_size: (usize, usize), options: &Options) -> Self {
+        let (mask_width, mask_height) = mask_size;
+        let (width, height) = options.sprite_size(mask_width, mask_height);
+
+        // Symmetries around a square only use the top-left square of the mask
+        let square = mask_width.min(mask_height);
+
+        let source = |x: usize, y: usize| -> Option<(usize, usize)> {
+            match options.symmetry {
+                Symmetry::Mirror => Some((
+                    if x < mask_width { x } else { width - x - 1 },
+                    if y < mask_height { y } else { height - y - 1 },
+                )),
+                Symmetry::Rotate2 => Some(if x < mask_width {
+                    (x, y)
+                } else {
+                    (width - x - 1, height - y - 1)
+                }),
+                Symmetry::Rotate4 => Some(match (x < square, y < square) {
+                    (true, true) => (x, y),
+                    (false, true) => (y, width - x - 1),
+                    (false, false) => (width - x - 1, height - y - 1),
+                    (true, false) => (height - y - 1, x),
+                }),
+                Symmetry::Diagonal => Some(if x >= y { (x, y) } else { (y, x) }),
+                Symmetry::Radial(arms) => radial_source(x, y, mask_size, arms),
+            }
+        };
+
+        let sources = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| source(x, y).map(|(x, y)| x + y * mask_width))
+            .collect();
+
+        Self {
+            width,
+            height,
+            sources,
+        }
+    }
+
+    /// Build the full sprite buffer from the mask buffer.
+    pub(crate) fn apply<T>(&self, buffer: &[T], empty: T) -> Vec<T>
+    where
+        T: Copy,
+    {
+        self.sources
+            .iter()
+            .map(|source| source.map_or(empty, |index| buffer[index]))
+            .collect()
+    }
+}
+
+/// Size of the full sprite.
+pub(crate) fn sprite_size(mask_size: (usize, usize), options: &Options) -> (usize, usize) {
+    let (mask_width, mask_height) = mask_size;
+    let square = mask_width.min(mask_height);
+
+    match options.symmetry {
+        Symmetry::Mirror => {
+            // The axis is shared between both halves so it shouldn't be counted twice
+            let mirrored = |size: usize| {
+                if options.mirror_center {
+                    (size * 2).saturating_sub(1)
+                } else {
+                    size * 2
+                }
+            };
+
+            (
+                if options.mirror_x {
+                    mirrored(mask_width)
+                } else {
+                    mask_width
+                },
+                if options.mirror_y {
+                    mirrored(mask_height)
+                } else {
+                    mask_height
+                },
+            )
+        }
+        Symmetry::Rotate2 => (mask_width * 2, mask_height),
+        Symmetry::Rotate4 => (square * 2, square * 2),
+        Symmetry::Diagonal => (square, square),
+        Symmetry::Radial(_) => (mask_height * 2, mask_height * 2),
+    }
+}
+
+/// Position of the center of the full sprite in the coordinates of the mask.
+pub(crate) fn mask_center(mask_size: (usize, usize), options: &Options) -> (f32, f32) {
+    let (mask_width, mask_height) = (mask_size.0 as f32, mask_size.1 as f32);
+    let square = mask_width.min(mask_height);
+
+    match options.symmetry {
+        Symmetry::Mirror => {
+            // The mirror axis is the edge of the mask, or the middle of the shared cells
+            let center = |size: f32, mirrored: bool| match (mirrored, options.mirror_center) {
+                (true, true) => size - 0.5,
+                (true, false) => size,
+                (false, _) => size / 2.0,
+            };
+
+            (
+                center(mask_width, options.mirror_x),
+                center(mask_height, options.mirror_y),
+            )
+        }
+        Symmetry::Rotate2 => (mask_width, mask_height / 2.0),
+        Symmetry::Rotate4 => (square, square),
+        Symmetry::Diagonal => (square / 2.0, square / 2.0),
+        Symmetry::Radial(_) => (mask_width, mask_height),
+    }
+}
+
+/// Which cell of the mask a pixel of a radial sprite is copied from.
+fn radial_source(
+    x: usize,
+    y: usize,
+    (mask_width, mask_height): (usize, usize),
+    arms: u8,
+) -> Option<(usize, usize)> {
+    // Position relative to the center of the sprite
+    let dx = x as f32 + 0.5 - mask_height as f32;
+    let dy = y as f32 + 0.5 - mask_height as f32;
+
+    let radius = (dx * dx + dy * dy).sqrt() as usize;
+    if radius >= mask_height {
+        return None;
+    }
+
+    // Angle clockwise from pointing up, from `0.0` to `1.0`
+    let angle = dx.atan2(-dy) / std::f32::consts::TAU;
+    let angle = angle.rem_euclid(1.0);
+
+    // Position within a single arm, folded so `1.0` is the middle of the arm
+    let arm = (angle * arms.max(1) as f32).fract();
+    let fold = 1.0 - (arm * 2.0 - 1.0).abs();
+
+    let mask_x = ((fold * mask_width as f32) as usize).min(mask_width - 1);
+    let mask_y = mask_height - radius - 1;
+
+    Some((mask_x, mask_y))
+}
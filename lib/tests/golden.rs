@@ -0,0 +1,125 @@
+//! Golden images of the deterministic output, these must be the same on every platform.
+
+use sprite_gen::{
+    generate, ColorMode, ColorRange, DitherPattern, Dithering, Gradient, HueBands, Mask, Options,
+    Palette, PixelFormat,
+};
+
+/// A small spaceship with all kinds of cells.
+const SHIP: &str = "
+......
+....#1
+...#11
+..#12*
+.#1122
+#11aed
+.##.mm
+";
+
+/// Generate the ship with deterministic colors.
+fn ship(options: Options) -> Vec<u32> {
+    let mask: Mask = SHIP.parse().unwrap();
+
+    generate(
+        mask.cells(),
+        mask.width(),
+        Options {
+            mirror_x: true,
+            deterministic: true,
+            pixel_format: PixelFormat::Rgba,
+            ..options
+        },
+    )
+    .into_pixels()
+}
+
+/// FNV-1a hash of all pixels, so big images don't have to be written out.
+fn hash(pixels: &[u32]) -> u64 {
+    pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_be_bytes())
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
+#[test]
+fn default_pixels() {
+    #[rustfmt::skip]
+    let expected = [
+        0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+        0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x3F4241FF, 0x00000000, 0x00000000, 0x3F4241FF, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+        0x00000000, 0x00000000, 0x00000000, 0x474848FF, 0x3E4140FF, 0x2D3433FF, 0x2D3433FF, 0x3E4140FF, 0x474848FF, 0x00000000, 0x00000000, 0x00000000,
+        0x00000000, 0x00000000, 0x3B3F3EFF, 0xAABDBAFF, 0xD6DFDDFF, 0xD393FFFF, 0xD393FFFF, 0xD6DFDDFF, 0xAABDBAFF, 0x3B3F3EFF, 0x00000000, 0x00000000,
+        0x00000000, 0x28302FFF, 0x404342FF, 0xD4DEDCFF, 0x343938FF, 0x2D3433FF, 0x2D3433FF, 0x343938FF, 0xD4DEDCFF, 0x404342FF, 0x28302FFF, 0x00000000,
+        0x030404FF, 0x252E2CFF, 0xAFC1BEFF, 0xDEB5E6FF, 0xA0122BFF, 0x273230FF, 0x273230FF, 0xA0122BFF, 0xDEB5E6FF, 0xAFC1BEFF, 0x252E2CFF, 0x030404FF,
+        0x00000000, 0x29302FFF, 0x2F3635FF, 0x373C3BFF, 0x2F3535FF, 0x5F6362FF, 0x5F6362FF, 0x2F3535FF, 0x373C3BFF, 0x2F3635FF, 0x29302FFF, 0x00000000,
+    ];
+
+    assert_eq!(ship(Options::default()), expected);
+}
+
+#[test]
+fn options_hashes() {
+    let palette = Palette::new(vec![
+        [0x0F, 0x38, 0x0F],
+        [0x30, 0x62, 0x30],
+        [0x8B, 0xAC, 0x0F],
+        [0x9B, 0xBC, 0x0F],
+    ]);
+
+    let cases = [
+        Options {
+            seed: 1,
+            ..Default::default()
+        },
+        Options {
+            seed: 2,
+            gradient: Gradient::Radial,
+            hue_bands: HueBands::Region,
+            color_variations: 0.8,
+            ..Default::default()
+        },
+        Options {
+            seed: 3,
+            gradient: Gradient::Angle(30.0),
+            base_color: Some([0xC0, 0x40, 0x20]),
+            ..Default::default()
+        },
+        Options {
+            seed: 4,
+            color_range: ColorRange {
+                hue_start: 0.9,
+                hue_end: 0.1,
+                min_lightness: 0.2,
+                max_lightness: 0.9,
+                ..Default::default()
+            },
+            dithering: Dithering {
+                pattern: DitherPattern::Bayer4,
+                strength: 1.0,
+            },
+            ..Default::default()
+        },
+        Options {
+            seed: 5,
+            color_mode: ColorMode::Palette(palette),
+            dithering: Dithering {
+                pattern: DitherPattern::Bayer2,
+                strength: 0.5,
+            },
+            ..Default::default()
+        },
+    ];
+
+    assert_eq!(
+        cases.map(|options| hash(&ship(options))),
+        [
+            0x77984C3B08405DCD,
+            0x58A0C2870A36174D,
+            0x48A4223D764E6D0D,
+            0xD414075F6B7690E4,
+            0x401B55DBF5041397,
+        ]
+    );
+}
@@ -0,0 +1,56 @@
+//! Bugs that were fixed, these are too specific for the examples in the documentation.
+
+use sprite_gen::{gen_sprite, generate, generate_indexed, Mask, MaskValue, Options, PixelFormat};
+
+#[test]
+fn ragged_mask_drops_the_last_row() {
+    let buffer = gen_sprite(&[1i8; 7], 3, Options::default());
+    assert_eq!(buffer.len(), 3 * 2);
+}
+
+#[test]
+fn resize_mask_without_cells() {
+    assert_eq!(Mask::new(0, 0).resize(2, 2), Mask::new(2, 2));
+}
+
+#[test]
+fn indexed_body_with_more_colors_than_slots() {
+    let mask = vec![MaskValue::Body1; 64 * 64];
+    let sprite = generate_indexed(
+        &mask,
+        64,
+        Options {
+            brightness_noise: 1.0,
+            ..Default::default()
+        },
+    );
+    let layout = sprite.layout();
+
+    assert!(layout.body.end > layout.body.start);
+    assert!(sprite
+        .indices()
+        .iter()
+        .all(|index| *index == 0 || layout.edge.contains(index) || layout.body.contains(index)));
+}
+
+#[test]
+fn indexed_materials_are_not_merged() {
+    // Every body color is unique, so the body has more colors than there are slots
+    let mut mask = vec![MaskValue::Body1; 64 * 64];
+    mask[32 + 32 * 64] = MaskValue::Emissive;
+    let options = Options {
+        brightness_noise: 1.0,
+        pixel_format: PixelFormat::Rgba,
+        ..Default::default()
+    };
+
+    let indexed = generate_indexed(&mask, 64, options.clone());
+    let index = indexed.get(32, 32).unwrap();
+    assert!(indexed.layout().emissive.contains(&index));
+
+    let [r, g, b, _] = generate(&mask, 64, options)
+        .get(32, 32)
+        .unwrap()
+        .to_be_bytes();
+    assert_eq!(indexed.palette().colors()[index as usize], [r, g, b]);
+}
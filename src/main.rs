@@ -6,17 +6,27 @@ mod sprites;
 mod widgets;
 mod window;
 
-use std::{future::Future, sync::OnceLock};
+use std::{
+    future::Future,
+    sync::{
+        mpsc::{Receiver, Sender},
+        OnceLock,
+    },
+};
 
 use assets::Assets;
 use assets_manager::{loader::TomlLoader, Asset, AssetGuard};
 use font::Font;
+use image::ImageOutputFormat;
 use input::Input;
 use miette::Result;
 use rfd::AsyncFileDialog;
 use serde::Deserialize;
 use sprite::Sprite;
-use sprite_gen::{MaskValue, Options};
+use sprite_gen::{
+    Cleanup, ColorMode, ColorSpace, Dithering, EdgeColors, Gradient, Light, MaskValue, Options,
+    Outline, Palette, PaletteFormat, PixelFormat, Sampling, Shading, Symmetry,
+};
 use sprites::Sprites;
 use taffy::{
     prelude::{Node, Rect, Size},
@@ -50,10 +60,38 @@ struct State {
     clear_canvas_button: Button,
     /// Button to save the sheet.
     save_sheet_button: Button,
+    /// Button to save the normal maps of the sheet.
+    save_normals_button: Button,
+    /// Button to load a palette file.
+    load_palette_button: Button,
+    /// Palette the sprites are constrained to.
+    palette: Option<Palette>,
+    /// Send loaded palettes from the async file dialog.
+    palette_sender: Sender<Palette>,
+    /// Receive loaded palettes from the async file dialog.
+    palette_receiver: Receiver<Palette>,
     /// Radio button group for the brush.
-    brush_radio: Radio<4>,
+    brush_radio: Radio<9>,
     /// Options checkbox group.
-    options_group: CheckboxGroup<3>,
+    options_group: CheckboxGroup<7>,
+    /// Radio button group for the symmetry.
+    symmetry_radio: Radio<5>,
+    /// Radio button group for the gradient direction.
+    gradient_radio: Radio<4>,
+    /// Slider for the amount of arms of radial symmetry.
+    radial_arms_slider: Slider,
+    /// Slider for the frequency of the noise used for sampling the shape.
+    noise_frequency_slider: Slider,
+    /// Slider for the amount of smoothing iterations.
+    smoothing_slider: Slider,
+    /// Slider for the minimum size of the parts of the shape.
+    min_island_size_slider: Slider,
+    /// Slider for the thickness of the outline.
+    outline_slider: Slider,
+    /// Slider for the strength of the light on the edges.
+    light_slider: Slider,
+    /// Slider for the strength of the shading of the body.
+    shading_slider: Slider,
     /// Selected brush type.
     brush: MaskValue,
     /// Slider for edge brightness.
@@ -132,12 +170,14 @@ impl State {
         };
 
         let brush_radio = Radio::new(
-            ["Solid", "Empty", "Body1", "Body2"],
+            [
+                "Solid", "Empty", "Body1", "Body2", "Emissive", "Accent", "Eye", "Metal", "Detail",
+            ],
             Some("Brush".to_string()),
             0,
             layout
                 .new_leaf(Style {
-                    min_size: Size::from_points(80.0, 150.0),
+                    min_size: Size::from_points(80.0, 250.0),
                     ..Default::default()
                 })
                 .unwrap(),
@@ -145,16 +185,122 @@ impl State {
         let brush = MaskValue::Solid;
 
         let options_group = CheckboxGroup::new(
-            [("Colored", true), ("Mirror X", true), ("Mirror Y", false)],
+            [
+                ("Colored", true),
+                ("Mirror X", true),
+                ("Mirror Y", false),
+                ("Center", false),
+                ("Selout", false),
+                ("OKLCH", false),
+                ("Dither", false),
+            ],
             Some("Options".to_string()),
             layout
                 .new_leaf(Style {
-                    min_size: Size::from_points(100.0, 120.0),
+                    min_size: Size::from_points(100.0, 230.0),
+                    ..Default::default()
+                })
+                .unwrap(),
+        );
+
+        let symmetry_radio = Radio::new(
+            ["Mirror", "Rotate 2", "Rotate 4", "Diagonal", "Radial"],
+            Some("Symmetry".to_string()),
+            0,
+            layout
+                .new_leaf(Style {
+                    min_size: Size::from_points(80.0, 170.0),
                     ..Default::default()
                 })
                 .unwrap(),
         );
 
+        let gradient_radio = Radio::new(
+            ["Random", "Horizontal", "Vertical", "Radial"],
+            Some("Gradient".to_string()),
+            0,
+            layout
+                .new_leaf(Style {
+                    min_size: Size::from_points(80.0, 150.0),
+                    ..Default::default()
+                })
+                .unwrap(),
+        );
+
+        let radial_arms_slider = Slider {
+            node: layout.new_leaf(slider_style.clone()).unwrap(),
+            length: 80.0,
+            value_label: Some("Radial Arms".to_string()),
+            min: 1.0,
+            max: 12.0,
+            steps: Some(11.0),
+            pos: 5.0 / 11.0,
+            ..Default::default()
+        };
+
+        let noise_frequency_slider = Slider {
+            node: layout.new_leaf(slider_style.clone()).unwrap(),
+            length: 80.0,
+            value_label: Some("Shape Noise".to_string()),
+            min: 0.0,
+            max: 50.0,
+            pos: 0.0,
+            ..Default::default()
+        };
+
+        let smoothing_slider = Slider {
+            node: layout.new_leaf(slider_style.clone()).unwrap(),
+            length: 80.0,
+            value_label: Some("Smoothing".to_string()),
+            min: 0.0,
+            max: 4.0,
+            steps: Some(4.0),
+            pos: 0.0,
+            ..Default::default()
+        };
+
+        let min_island_size_slider = Slider {
+            node: layout.new_leaf(slider_style.clone()).unwrap(),
+            length: 80.0,
+            value_label: Some("Min Island Size".to_string()),
+            min: 0.0,
+            max: 16.0,
+            steps: Some(16.0),
+            pos: 0.0,
+            ..Default::default()
+        };
+
+        let outline_slider = Slider {
+            node: layout.new_leaf(slider_style.clone()).unwrap(),
+            length: 80.0,
+            value_label: Some("Outline".to_string()),
+            min: 0.0,
+            max: 3.0,
+            steps: Some(3.0),
+            pos: 1.0 / 3.0,
+            ..Default::default()
+        };
+
+        let light_slider = Slider {
+            node: layout.new_leaf(slider_style.clone()).unwrap(),
+            length: 80.0,
+            value_label: Some("Light".to_string()),
+            min: 0.0,
+            max: 100.0,
+            pos: 0.0,
+            ..Default::default()
+        };
+
+        let shading_slider = Slider {
+            node: layout.new_leaf(slider_style.clone()).unwrap(),
+            length: 80.0,
+            value_label: Some("Shading".to_string()),
+            min: 0.0,
+            max: 100.0,
+            pos: 0.0,
+            ..Default::default()
+        };
+
         let edge_brightness_slider = Slider {
             node: layout.new_leaf(slider_style.clone()).unwrap(),
             length: 80.0,
@@ -201,6 +347,19 @@ impl State {
             ..Default::default()
         };
 
+        let save_normals_button = Button {
+            node: layout.new_leaf(button_style.clone()).unwrap(),
+            label: Some("Save Normals".to_string()),
+            ..Default::default()
+        };
+
+        let load_palette_button = Button {
+            node: layout.new_leaf(button_style.clone()).unwrap(),
+            label: Some("Load Palette".to_string()),
+            ..Default::default()
+        };
+        let (palette_sender, palette_receiver) = std::sync::mpsc::channel();
+
         let sprites = Sprites {
             offset: Vec2::new(5.0, 470.0),
             size: Extent2::new(
@@ -225,7 +384,12 @@ impl State {
                     gap,
                     ..Default::default()
                 },
-                &[options_group.node, brush_radio.node],
+                &[
+                    options_group.node,
+                    symmetry_radio.node,
+                    gradient_radio.node,
+                    brush_radio.node,
+                ],
             )
             .unwrap();
         let pixel_sliders = layout
@@ -294,7 +458,16 @@ impl State {
                     saturation_slider.node,
                     color_variations_slider.node,
                     brightness_noise_slider.node,
+                    radial_arms_slider.node,
+                    noise_frequency_slider.node,
+                    smoothing_slider.node,
+                    min_island_size_slider.node,
+                    outline_slider.node,
+                    light_slider.node,
+                    shading_slider.node,
                     save_sheet_button.node,
+                    save_normals_button.node,
+                    load_palette_button.node,
                 ],
             )
             .unwrap();
@@ -321,8 +494,22 @@ impl State {
             y_pixels_slider,
             clear_canvas_button,
             save_sheet_button,
+            save_normals_button,
+            load_palette_button,
+            palette: None,
+            palette_sender,
+            palette_receiver,
             brush_radio,
             options_group,
+            symmetry_radio,
+            gradient_radio,
+            radial_arms_slider,
+            noise_frequency_slider,
+            smoothing_slider,
+            min_island_size_slider,
+            outline_slider,
+            light_slider,
+            shading_slider,
             brush,
             edge_brightness_slider,
             color_variations_slider,
@@ -373,18 +560,21 @@ impl State {
                 Extent2::new(self.x_pixels_slider.value(), self.y_pixels_slider.value()).as_(),
             );
 
+            self.sprites.reseed();
             self.generate();
             self.update_layout();
         }
 
         // Allow user to draw
         if self.drawing_area.update(input, self.brush.clone()) {
+            self.sprites.reseed();
             self.generate();
         }
 
         if self.clear_canvas_button.update(input) {
             self.drawing_area.clear();
 
+            self.sprites.reseed();
             self.generate();
         }
 
@@ -403,6 +593,70 @@ impl State {
             });
         }
 
+        // Open the dialog to save the normal maps
+        if self.save_normals_button.update(input) {
+            let sheet = self
+                .sprites
+                .normal_map_sheet(self.drawing_area.mask(), self.options());
+
+            // Encode before the dialog so only the bytes have to be moved
+            let mut png = std::io::Cursor::new(Vec::new());
+            match sheet.write_to(&mut png, ImageOutputFormat::Png) {
+                Ok(()) => block_async(async move {
+                    if let Some(file_handle) = AsyncFileDialog::new()
+                        .set_title("Save Normal Maps")
+                        .add_filter("image", &["png"])
+                        .set_file_name("normals.png")
+                        .save_file()
+                        .await
+                    {
+                        if let Err(err) = file_handle.write(&png.into_inner()).await {
+                            log::error!("Could not save normal maps: {err}");
+                        }
+                    }
+                }),
+                Err(err) => log::error!("Could not encode normal maps: {err}"),
+            }
+        }
+
+        // Open the dialog to load a palette file
+        if self.load_palette_button.update(input) {
+            let palette_sender = self.palette_sender.clone();
+            block_async(async move {
+                if let Some(file_handle) = AsyncFileDialog::new()
+                    .set_title("Load Palette")
+                    .add_filter("palette", &["gpl", "hex", "txt", "pal"])
+                    .pick_file()
+                    .await
+                {
+                    let file_name = file_handle.file_name();
+                    let Some(format) = file_name
+                        .rsplit_once('.')
+                        .and_then(|(_, extension)| PaletteFormat::from_extension(extension))
+                    else {
+                        log::warn!("Unknown palette format for file '{file_name}'");
+                        return;
+                    };
+
+                    let contents = file_handle.read().await;
+                    match Palette::parse(&String::from_utf8_lossy(&contents), format) {
+                        Ok(palette) => {
+                            // The receiver only stops existing when the application is closed
+                            let _ = palette_sender.send(palette);
+                        }
+                        Err(err) => log::warn!("Error loading palette '{file_name}': {err}"),
+                    }
+                }
+            });
+        }
+
+        // Use the palette when it's loaded
+        if let Ok(palette) = self.palette_receiver.try_recv() {
+            self.palette = Some(palette);
+
+            self.generate();
+        }
+
         // Update the brush according to the radio group
         if let Some(selected) = self.brush_radio.update(input) {
             self.brush = match selected {
@@ -410,6 +664,11 @@ impl State {
                 1 => MaskValue::Empty,
                 2 => MaskValue::Body1,
                 3 => MaskValue::Body2,
+                4 => MaskValue::Emissive,
+                5 => MaskValue::Accent,
+                6 => MaskValue::Eye,
+                7 => MaskValue::Metal,
+                8 => MaskValue::Detail,
                 _ => panic!(),
             };
         }
@@ -418,6 +677,23 @@ impl State {
             self.generate();
         }
 
+        if self.symmetry_radio.update(input).is_some()
+            || self.radial_arms_slider.update(input)
+            || self.gradient_radio.update(input).is_some()
+        {
+            self.generate();
+        }
+
+        if self.noise_frequency_slider.update(input)
+            || self.smoothing_slider.update(input)
+            || self.min_island_size_slider.update(input)
+            || self.outline_slider.update(input)
+            || self.light_slider.update(input)
+            || self.shading_slider.update(input)
+        {
+            self.generate();
+        }
+
         if self.edge_brightness_slider.update(input)
             || self.color_variations_slider.update(input)
             || self.brightness_noise_slider.update(input)
@@ -434,8 +710,19 @@ impl State {
         self.y_pixels_slider.render(canvas);
         self.clear_canvas_button.render(canvas);
         self.save_sheet_button.render(canvas);
+        self.save_normals_button.render(canvas);
+        self.load_palette_button.render(canvas);
         self.brush_radio.render(canvas);
         self.options_group.render(canvas);
+        self.symmetry_radio.render(canvas);
+        self.gradient_radio.render(canvas);
+        self.radial_arms_slider.render(canvas);
+        self.noise_frequency_slider.render(canvas);
+        self.smoothing_slider.render(canvas);
+        self.min_island_size_slider.render(canvas);
+        self.outline_slider.render(canvas);
+        self.light_slider.render(canvas);
+        self.shading_slider.render(canvas);
         self.sprites.render(canvas);
         self.edge_brightness_slider.render(canvas);
         self.color_variations_slider.render(canvas);
@@ -466,10 +753,36 @@ impl State {
             self.abs_location(self.save_sheet_button.node),
             self.layout.layout(self.save_sheet_button.node).unwrap(),
         );
+        self.save_normals_button.update_layout(
+            self.abs_location(self.save_normals_button.node),
+            self.layout.layout(self.save_normals_button.node).unwrap(),
+        );
+        self.load_palette_button.update_layout(
+            self.abs_location(self.load_palette_button.node),
+            self.layout.layout(self.load_palette_button.node).unwrap(),
+        );
         self.brush_radio
             .update_layout(self.abs_location(self.brush_radio.node));
         self.options_group
             .update_layout(self.abs_location(self.options_group.node));
+        self.symmetry_radio
+            .update_layout(self.abs_location(self.symmetry_radio.node));
+        self.gradient_radio
+            .update_layout(self.abs_location(self.gradient_radio.node));
+        self.radial_arms_slider
+            .update_layout(self.abs_location(self.radial_arms_slider.node));
+        self.noise_frequency_slider
+            .update_layout(self.abs_location(self.noise_frequency_slider.node));
+        self.smoothing_slider
+            .update_layout(self.abs_location(self.smoothing_slider.node));
+        self.min_island_size_slider
+            .update_layout(self.abs_location(self.min_island_size_slider.node));
+        self.outline_slider
+            .update_layout(self.abs_location(self.outline_slider.node));
+        self.light_slider
+            .update_layout(self.abs_location(self.light_slider.node));
+        self.shading_slider
+            .update_layout(self.abs_location(self.shading_slider.node));
         self.edge_brightness_slider
             .update_layout(self.abs_location(self.edge_brightness_slider.node));
         self.saturation_slider
@@ -482,15 +795,17 @@ impl State {
 
     /// Generate new sprites.
     pub fn generate(&mut self) {
+        let options = self.options();
+
         // Scale to fill the rectangle with the lowest factor
         let area = Extent2::new(SIZE.w - 10, SIZE.h - self.sprites.offset.y as usize - 10);
-        let width = self.x_pixels_slider.value() as usize
-            * if self.options_group.checked(1) { 2 } else { 1 }
-            + 4;
+        let (width, height) = options.sprite_size(
+            self.x_pixels_slider.value() as usize,
+            self.y_pixels_slider.value() as usize,
+        );
+        let width = width + 4;
         let x_factor = area.w / width / settings().preview_requested.w;
-        let height = self.y_pixels_slider.value() as usize
-            * if self.options_group.checked(2) { 2 } else { 1 }
-            + 4;
+        let height = height + 4;
         let y_factor = area.h / height / settings().preview_requested.h;
         let scale = x_factor.min(y_factor).max(2);
 
@@ -498,21 +813,95 @@ impl State {
         let amount = Extent2::new(area.w / width / scale, area.h / height / scale);
 
         // Redraw all sprites
-        self.sprites.generate(
-            self.drawing_area.mask(),
-            Options {
-                colored: self.options_group.checked(0),
-                mirror_x: self.options_group.checked(1),
-                mirror_y: self.options_group.checked(2),
-                edge_brightness: self.edge_brightness_slider.value() as f32 / 100.0,
-                color_variations: self.color_variations_slider.value() as f32 / 100.0,
-                brightness_noise: self.brightness_noise_slider.value() as f32 / 100.0,
-                saturation: self.saturation_slider.value() as f32 / 100.0,
+        self.sprites
+            .generate(self.drawing_area.mask(), options, amount, scale);
+    }
+
+    /// Build the generator options from the widgets.
+    pub fn options(&self) -> Options {
+        Options {
+            colored: self.options_group.checked(0),
+            mirror_x: self.options_group.checked(1),
+            mirror_y: self.options_group.checked(2),
+            mirror_center: self.options_group.checked(3),
+            symmetry: match self.symmetry_radio.selected {
+                0 => Symmetry::Mirror,
+                1 => Symmetry::Rotate2,
+                2 => Symmetry::Rotate4,
+                3 => Symmetry::Diagonal,
+                4 => Symmetry::Radial(self.radial_arms_slider.value().round() as u8),
+                _ => panic!(),
+            },
+            gradient: match self.gradient_radio.selected {
+                0 => Gradient::Random,
+                1 => Gradient::Horizontal,
+                2 => Gradient::Vertical,
+                3 => Gradient::Radial,
+                _ => panic!(),
+            },
+            dithering: Dithering {
+                strength: if self.options_group.checked(6) {
+                    1.0
+                } else {
+                    0.0
+                },
                 ..Default::default()
             },
-            amount,
-            scale,
-        );
+            color_space: if self.options_group.checked(5) {
+                ColorSpace::Oklch
+            } else {
+                ColorSpace::Hsl
+            },
+            // Disable the noise when the slider is at zero
+            sampling: match self.noise_frequency_slider.value() as f32 / 100.0 {
+                frequency if frequency > 0.0 => Sampling::Noise {
+                    frequency,
+                    threshold: 0.5,
+                },
+                _ => Sampling::Independent,
+            },
+            cleanup: Cleanup {
+                iterations: self.smoothing_slider.value().round() as usize,
+                min_island_size: self.min_island_size_slider.value().round() as usize,
+                ..Default::default()
+            },
+            outline: Outline {
+                thickness: self.outline_slider.value().round() as usize,
+                colors: if self.options_group.checked(4) {
+                    EdgeColors::Selective
+                } else {
+                    EdgeColors::Own
+                },
+                ..Default::default()
+            },
+            // Light from the top-left, disabled when the slider is at zero
+            light: match self.light_slider.value() as f32 / 100.0 {
+                strength if strength > 0.0 => Some(Light {
+                    angle: 315.0,
+                    strength,
+                }),
+                _ => None,
+            },
+            shading: {
+                let strength = self.shading_slider.value() as f32 / 100.0;
+
+                Shading {
+                    rounding: strength,
+                    highlights: strength,
+                    occlusion: strength,
+                }
+            },
+            edge_brightness: self.edge_brightness_slider.value() as f32 / 100.0,
+            color_variations: self.color_variations_slider.value() as f32 / 100.0,
+            brightness_noise: self.brightness_noise_slider.value() as f32 / 100.0,
+            saturation: self.saturation_slider.value() as f32 / 100.0,
+            pixel_format: PixelFormat::Argb,
+            color_mode: self
+                .palette
+                .clone()
+                .map_or(ColorMode::Free, ColorMode::Palette),
+            ..Default::default()
+        }
     }
 
     /// Get absolute coordinates for a node.
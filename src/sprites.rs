@@ -1,5 +1,6 @@
 use blit::{prelude::Size, Blit, BlitBuffer, BlitOptions};
-use sprite_gen::{MaskValue, Options};
+use image::RgbaImage;
+use sprite_gen::{MaskValue, Options, PixelFormat};
 use vek::{Extent2, Vec2};
 
 use crate::SIZE;
@@ -15,6 +16,8 @@ pub struct Sprites {
     pub size: Extent2<usize>,
     /// Amount of sprites in each dimension.
     pub amount: Extent2<usize>,
+    /// Seed of each sprite, kept so changing the colors doesn't change the shapes.
+    pub seeds: Vec<u64>,
 }
 
 impl Sprites {
@@ -44,22 +47,17 @@ impl Sprites {
         scale: usize,
     ) {
         self.amount = amount;
-        self.sprites = (0..(self.amount.product()))
-            .map(|_| {
+        self.seeds
+            .resize_with(self.amount.product(), || fastrand::u64(0..u64::MAX));
+        self.sprites = self
+            .seeds
+            .iter()
+            .map(|&seed| {
                 // Generate sprite
-                options.seed = fastrand::u64(0..u64::MAX);
-                let buf = sprite_gen::gen_sprite(mask, self.size.w, options);
-
-                let width = if options.mirror_x {
-                    self.size.w * 2
-                } else {
-                    self.size.w
-                };
-                let height = if options.mirror_y {
-                    self.size.h * 2
-                } else {
-                    self.size.h
-                };
+                options.seed = seed;
+                let sprite = sprite_gen::generate(mask, self.size.w, options.clone());
+                let (width, height) = sprite.size();
+                let buf = sprite.pixels();
 
                 // Buffer for the scaled pixels
                 let mut scaled_buf = vec![0; buf.len() * scale * scale];
@@ -78,11 +76,50 @@ impl Sprites {
                 }
 
                 // Convert to blit buffer so it's easier to draw
-                BlitBuffer::from_buffer(&scaled_buf, width * scale, 0)
+                BlitBuffer::from_buffer(&scaled_buf, width * scale, 127)
             })
             .collect();
     }
 
+    /// Generate the normal maps of each sprite in a single image, in the same layout as rendered.
+    pub fn normal_map_sheet(&self, mask: &[MaskValue], mut options: Options) -> RgbaImage {
+        options.normal_map = true;
+        options.pixel_format = PixelFormat::Rgba;
+
+        let (width, height) = options.sprite_size(self.size.w, self.size.h);
+        let mut sheet = RgbaImage::new(
+            ((width + 1) * self.amount.w) as u32,
+            ((height + 1) * self.amount.h) as u32,
+        );
+        for (index, &seed) in self.seeds.iter().enumerate() {
+            options.seed = seed;
+            let Some(normals) =
+                sprite_gen::generate(mask, self.size.w, options.clone()).normal_map()
+            else {
+                continue;
+            };
+
+            let x = (index % self.amount.w) * (width + 1);
+            let y = (index / self.amount.w) * (height + 1);
+            for (row_index, row) in normals.rows().enumerate() {
+                for (column, pixel) in row.iter().enumerate() {
+                    sheet.put_pixel(
+                        (x + column) as u32,
+                        (y + row_index) as u32,
+                        image::Rgba(pixel.to_be_bytes()),
+                    );
+                }
+            }
+        }
+
+        sheet
+    }
+
+    /// Pick new seeds so the next generation results in different shapes.
+    pub fn reseed(&mut self) {
+        self.seeds.clear();
+    }
+
     /// Resize the size of the canvas.
     pub fn resize(&mut self, size: Extent2<usize>) {
         self.size = size;
@@ -96,6 +133,7 @@ impl Default for Sprites {
             sprites: Vec::new(),
             size: Extent2::zero(),
             amount: Extent2::zero(),
+            seeds: Vec::new(),
         }
     }
 }
@@ -162,6 +162,11 @@ impl Grid {
             MaskValue::Empty => 0xFFFFFFFF,
             MaskValue::Body1 => 0xFFFF9999,
             MaskValue::Body2 => 0xFF9999FF,
+            MaskValue::Emissive => 0xFFFFDD44,
+            MaskValue::Accent => 0xFF99FF99,
+            MaskValue::Eye => 0xFF44DDFF,
+            MaskValue::Metal => 0xFFBBBBBB,
+            MaskValue::Detail => 0xFF886644,
         }
     }
 }